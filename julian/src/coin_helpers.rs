@@ -1,68 +1,136 @@
 use crate::error::ContractError;
-use cosmwasm_std::Coin;
+use cosmwasm_std::{
+    attr, coin, to_json_binary, Addr, Attribute, BankMsg, Coin, CosmosMsg, StdResult, Uint128,
+    WasmMsg,
+};
+use cw20::Cw20ExecuteMsg;
 
-pub fn assert_sent_exact_coin(
-    sent: &[Coin],
-    required: Option<Vec<Coin>>,
-) -> Result<(), ContractError> {
-    if let Some(required_coins) = required {
-        let mut required_amounts = Vec::new();
-        for coin in required_coins {
-            required_amounts.push((coin.denom.to_string(), coin.amount.u128()));
-        }
-        let mut received_amounts = Vec::new();
-        for coin in sent {
-            let mut found = false;
-            for (denom, amount) in received_amounts.iter_mut() {
-                if denom == &coin.denom.to_string() {
-                    *amount += coin.amount.u128();
-                    found = true;
-                    break;
-                }
-            }
-            if !found {
-                received_amounts.push((coin.denom.to_string(), coin.amount.u128()));
-            }
+//cw20 tokens share the denom namespace with native coins as "cw20:<token contract>"
+pub const CW20_PREFIX: &str = "cw20:";
+
+pub fn cw20_denom(token_contract: &Addr) -> String {
+    format!("{}{}", CW20_PREFIX, token_contract)
+}
+
+//returns the token contract address when the denom is a cw20 denom
+pub fn cw20_contract(denom: &str) -> Option<&str> {
+    denom.strip_prefix(CW20_PREFIX)
+}
+
+//pays out `amount` of `denom` as a bank send for native denoms and a cw20 transfer otherwise
+pub fn send_tokens(recipient: &str, amount: u128, denom: &str) -> StdResult<CosmosMsg> {
+    match cw20_contract(denom) {
+        Some(token_contract) => Ok(WasmMsg::Execute {
+            contract_addr: token_contract.to_string(),
+            msg: to_json_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: recipient.to_string(),
+                amount: Uint128::from(amount),
+            })?,
+            funds: vec![],
         }
-        let mut sent_sufficient_funds = false;
-        for (required_denom, required_amount) in required_amounts.iter() {
-            let mut found = false;
-            for (received_denom, received_amount) in received_amounts.iter() {
-                if required_denom == received_denom && *required_amount <= *received_amount {
-                    sent_sufficient_funds = true;
-                    found = true;
-                    break;
-                }
-            }
-            if !found {
-                break;
-            }
+        .into()),
+        None => Ok(BankMsg::Send {
+            to_address: recipient.to_string(),
+            amount: vec![coin(amount, denom)],
         }
-        if sent_sufficient_funds {
-            return Ok(());
-        } else {
-            let mut received_amount_strings = vec![];
-            for (denom, received_amount) in received_amounts.iter() {
-                received_amount_strings.push(received_amount.to_string() + " " + denom);
-            }
-            let mut required_amount_strings = vec![];
-            for (denom, required_amount) in required_amounts.iter() {
-                required_amount_strings.push(required_amount.to_string() + " " + denom);
-            }
-            return Err(ContractError::NotEnoughFunds {
-                needed: convert_vector_of_string_slices_to_string(required_amount_strings),
-                received: convert_vector_of_string_slices_to_string(received_amount_strings),
-            });
+        .into()),
+    }
+}
+
+//what a buyer paid against what was owed, and what goes back to them
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PaymentBreakdown {
+    pub denom: String,
+    pub required: u128,
+    pub received: u128,
+    pub overpayment: u128,
+    //overpayment plus every coin sent in a denom other than the required one
+    pub refund: Vec<Coin>,
+}
+
+impl PaymentBreakdown {
+    pub fn refund_msgs(&self, recipient: &str) -> StdResult<Vec<CosmosMsg>> {
+        self.refund
+            .iter()
+            .map(|refund| send_tokens(recipient, refund.amount.u128(), &refund.denom))
+            .collect()
+    }
+
+    pub fn attributes(&self) -> Vec<Attribute> {
+        vec![
+            attr("payment_denom", &self.denom),
+            attr("payment_required", self.required.to_string()),
+            attr("payment_received", self.received.to_string()),
+            attr("payment_overpayment", self.overpayment.to_string()),
+            attr("payment_refunded", coins_to_string(&self.refund)),
+        ]
+    }
+}
+
+//checks that at least `required` of `denom` was sent; anything beyond it, in any denom, is refunded
+pub fn check_payment(
+    sent: &[Coin],
+    denom: &str,
+    required: u128,
+) -> Result<PaymentBreakdown, ContractError> {
+    //the same denom can appear more than once in a funds list
+    let mut received_amounts: Vec<Coin> = vec![];
+    for sent_coin in sent.iter().filter(|sent_coin| !sent_coin.amount.is_zero()) {
+        match received_amounts
+            .iter_mut()
+            .find(|received| received.denom == sent_coin.denom)
+        {
+            Some(received) => received.amount += sent_coin.amount,
+            None => received_amounts.push(sent_coin.clone()),
         }
     }
-    Ok(())
+    let received = received_amounts
+        .iter()
+        .find(|received| received.denom == denom)
+        .map_or(0, |received| received.amount.u128());
+    if received < required {
+        return Err(ContractError::NotEnoughFunds {
+            needed: format!("{}{}", required, denom),
+            received: coins_to_string(&received_amounts),
+        });
+    }
+    let overpayment = received - required;
+    let mut refund: Vec<Coin> = received_amounts
+        .into_iter()
+        .filter(|received| received.denom != denom)
+        .collect();
+    if overpayment > 0 {
+        refund.insert(0, coin(overpayment, denom));
+    }
+    Ok(PaymentBreakdown {
+        denom: denom.to_string(),
+        required,
+        received,
+        overpayment,
+        refund,
+    })
+}
+
+//like check_payment, but everything sent in `denom` counts as the bid and only other denoms are refunded
+pub fn check_bid(
+    sent: &[Coin],
+    denom: &str,
+    minimum: u128,
+) -> Result<PaymentBreakdown, ContractError> {
+    let mut payment = check_payment(sent, denom, minimum)?;
+    payment.required = payment.received;
+    payment.overpayment = 0;
+    payment.refund.retain(|refund| refund.denom != denom);
+    Ok(payment)
 }
 
-fn convert_vector_of_string_slices_to_string(vector: Vec<String>) -> String {
-    let mut string = String::new();
-    for s in vector {
-        string.push_str(&s);
-        string.push_str(", ");
+fn coins_to_string(coins: &[Coin]) -> String {
+    if coins.is_empty() {
+        return "none".to_string();
     }
-    string
+    coins
+        .iter()
+        .map(|c| c.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
 }
@@ -1,50 +1,96 @@
 use cosmwasm_std::{
-    coin, entry_point, to_json_binary, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Order,
-    Response, StdError, StdResult,
+    attr, coin, entry_point, from_json, to_json_binary, Addr, Api, Attribute, Binary, Coin,
+    CosmosMsg, Deps, DepsMut, Env, MessageInfo, Order as StorageOrder, Response, StdError,
+    StdResult, Storage, Timestamp, Uint128, Uint64,
 };
 use cw2::{get_contract_version, set_contract_version};
-use cw_storage_plus::Bound;
+use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, Cw20ReceiveMsg};
+use cw_storage_plus::{Bound, Map};
 use is_false::is_false;
+use sha2::{Digest, Sha256};
+use std::collections::BTreeMap;
 use std::env;
 
-use crate::coin_helpers::assert_sent_exact_coin;
+use crate::coin_helpers::{
+    check_bid, check_payment, cw20_contract, cw20_denom, send_tokens, PaymentBreakdown,
+};
 use crate::error::ContractError;
+use crate::fees::{
+    fee_recipients_to_string, split_by_bps, split_fee, validate_fee_schedule, BPS_DENOMINATOR,
+};
 use crate::msg::{
-    AllListingsResponse, ArbitrationListingsResponse, ExecuteMsg, InstantiateMsg,
-    ListingCountResponse, ListingResponse, MigrateMsg, QueryMsg, SearchListingsResponse,
-    ProfileResponse,
+    AllListingsResponse, ArbiterMsg, ArbiterResponse, ArbitersResponse,
+    ArbitrationListingsResponse, BalanceResponse, BidsResponse, ConfigResponse,
+    CurrentPriceResponse, DenomEscrowSummary, EscrowSummaryResponse, ExecuteMsg,
+    FeePreviewResponse, FeeRecipientMsg, InstantiateMsg, ListingCountResponse, ListingResponse,
+    MigrateMsg, MilestoneMsg, OfferResponse, OffersResponse, OrderResponse, OrdersResponse,
+    ProfileResponse, ProposalsResponse, QueryMsg, ReceiveMsg, RulingMsg, RulingsResponse,
+    SealedBidResponse, SearchListingsResponse, SettlementsResponse, ShippingOptionMsg, VariantMsg,
+    WantedAdResponse, WantedAdsResponse,
 };
 use crate::state::{
-    Config, Listing, CONFIG, LAST_LISTING_ID, LISTING, LISTING_COUNT, LISTING_TITLES,
-    Profile, PROFILE_NAME, PROFILES, Relationship, RELATIONSHIPS,
+    Arbiter, Bid, Config, DutchAuction, EnglishAuction, Listing, ListingStatus, Milestone,
+    MilestoneStatus, MilestoneTerms, Offer, OfferStatus, Order, OrderStatus, Profile, Proposal,
+    Relationship, Ruling, SaleType, SealedBid, SealedBidAuction, Service, Settlement,
+    ShippingOption, Variant, WantedAd, WantedAdStatus, ARBITERS, BALANCES, BIDS, CONFIG, ESCROWED,
+    LAST_LISTING_ID, LAST_OFFER_ID, LAST_ORDER_ID, LAST_WANTED_AD_ID, LISTING, LISTING_COUNT,
+    LISTING_TITLES, OFFERS, OPEN_OFFERS, ORDERS, ORDERS_BY_ADDRESS, OWED, PENDING_ADMIN, PROFILES,
+    PROFILE_NAME, PROPOSALS, RELATIONSHIPS, RULINGS, SEALED_BIDS, SETTLEMENTS, SHIPMENTS,
+    WANTED_ADS, WANTED_ADS_BY_REQUESTER, WANTED_ADS_BY_TAG,
 };
 
 const CONTRACT_NAME: &str = env!("CARGO_PKG_NAME");
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
-const ADMIN: &str = "cosmos1qa6supftg80qh93u6894lsg4q4m25ftgfsadtw";
-const MAX_ID_LENGTH: usize = 128;
-const MAX_TEXT_LENGTH: usize = 499;
-const IPFS: &str = "https://gateway.pinata.cloud/ipfs/";
-const ATOM: &str = "uatom";
-const ARBITERS: [&str; 1] = ["cosmos1hgrhzkhfjr4rkumxzcrnpddd4qmddnc4lsmezc"];
+//starting limits, adjustable by the admin through UpdateConfig
+const DEFAULT_MAX_ID_LENGTH: u64 = 128;
+const DEFAULT_MAX_TEXT_LENGTH: u64 = 499;
+//first version storing sales as orders, see migrate
+const FIRST_ORDERS_VERSION: &str = "0.1.0";
 
 #[entry_point]
 pub fn instantiate(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    _msg: InstantiateMsg,
+    msg: InstantiateMsg,
 ) -> Result<Response, ContractError> {
     set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+    let admin = match msg.admin {
+        Some(admin) => deps.api.addr_validate(&admin)?,
+        None => info.sender,
+    };
+    validate_denoms(deps.api, &msg.accepted_denoms)?;
+    let fee_recipients = validate_fee_schedule(deps.api, msg.fee_bps, msg.fee_recipients)?;
+    validate_window("confirmation_window", msg.confirmation_window)?;
+    validate_window("ship_window", msg.ship_window)?;
+    if msg.max_arbiter_fee_bps as u128 > BPS_DENOMINATOR {
+        return Err(ContractError::InvalidFeeSchedule {});
+    }
+    for arbiter in msg.arbiters {
+        save_arbiter(deps.storage, deps.api, msg.max_arbiter_fee_bps, arbiter)?;
+    }
     let config = Config {
-        admin: info.sender.clone(),
+        admin: admin.clone(),
+        fee_bps: msg.fee_bps,
+        fee_recipients,
+        accepted_denoms: msg.accepted_denoms,
+        gateway: msg.gateway,
+        max_text_length: DEFAULT_MAX_TEXT_LENGTH,
+        max_id_length: DEFAULT_MAX_ID_LENGTH,
+        push_payouts: msg.push_payouts,
+        confirmation_window: msg.confirmation_window,
+        ship_window: msg.ship_window,
+        max_arbiter_fee_bps: msg.max_arbiter_fee_bps,
     };
     CONFIG.save(deps.storage, &config)?;
     LAST_LISTING_ID.save(deps.storage, &0)?;
     LISTING_COUNT.save(deps.storage, &0)?;
+    LAST_ORDER_ID.save(deps.storage, &0)?;
+    LAST_OFFER_ID.save(deps.storage, &0)?;
+    LAST_WANTED_AD_ID.save(deps.storage, &0)?;
     Ok(Response::new()
         .add_attribute("action", "instantiate")
-        .add_attribute("admin", info.sender.to_string()))
+        .add_attribute("admin", admin.to_string()))
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
@@ -62,6 +108,11 @@ pub fn execute(
             tags,
             contact,
             price,
+            denom,
+            quantity,
+            variants,
+            shipping_options,
+            ship_within,
         } => execute_create_listing(
             deps,
             env,
@@ -72,44 +123,395 @@ pub fn execute(
             tags,
             contact,
             price,
+            denom,
+            quantity,
+            variants,
+            shipping_options,
+            ship_within,
+            SaleType::FixedPrice,
         ),
+        ExecuteMsg::CreateAuction {
+            listing_title,
+            external_id,
+            text,
+            tags,
+            contact,
+            denom,
+            start_price,
+            reserve_price,
+            min_increment,
+            end_time,
+            extension_seconds,
+        } => {
+            if min_increment == 0 || end_time <= env.block.time {
+                return Err(ContractError::InvalidAuction {});
+            }
+            //a zero start would let a zero bid take the item
+            if start_price == 0 {
+                return Err(ContractError::InvalidPriceSchedule {});
+            }
+            let auction = EnglishAuction {
+                start_price,
+                reserve_price,
+                min_increment,
+                end_time,
+                extension_seconds,
+                highest_bid: None,
+                bid_count: 0,
+            };
+            execute_create_listing(
+                deps,
+                env,
+                info,
+                listing_title,
+                external_id,
+                text,
+                tags,
+                contact,
+                start_price,
+                denom,
+                1,
+                vec![],
+                vec![],
+                None,
+                SaleType::EnglishAuction(auction),
+            )
+        }
+        ExecuteMsg::CreateDutchAuction {
+            listing_title,
+            external_id,
+            text,
+            tags,
+            contact,
+            denom,
+            quantity,
+            start_price,
+            floor_price,
+            end_time,
+            step_seconds,
+        } => {
+            if start_price <= floor_price || end_time <= env.block.time {
+                return Err(ContractError::InvalidPriceSchedule {});
+            }
+            let auction = DutchAuction {
+                start_price,
+                floor_price,
+                start_time: env.block.time,
+                end_time,
+                step_seconds,
+            };
+            execute_create_listing(
+                deps,
+                env,
+                info,
+                listing_title,
+                external_id,
+                text,
+                tags,
+                contact,
+                start_price,
+                denom,
+                quantity,
+                vec![],
+                vec![],
+                None,
+                SaleType::DutchAuction(auction),
+            )
+        }
+        ExecuteMsg::CreateSealedBidAuction {
+            listing_title,
+            external_id,
+            text,
+            tags,
+            contact,
+            denom,
+            min_deposit,
+            reserve_price,
+            commit_end,
+            reveal_end,
+            forfeit_bps,
+        } => {
+            if commit_end <= env.block.time
+                || reveal_end <= commit_end
+                || forfeit_bps as u128 > BPS_DENOMINATOR
+            {
+                return Err(ContractError::InvalidSealedBidAuction {});
+            }
+            let auction = SealedBidAuction {
+                min_deposit,
+                reserve_price,
+                commit_end,
+                reveal_end,
+                forfeit_bps,
+                highest_bid: None,
+                commitment_count: 0,
+                bid_count: 0,
+                deposits_held: 0,
+            };
+            execute_create_listing(
+                deps,
+                env,
+                info,
+                listing_title,
+                external_id,
+                text,
+                tags,
+                contact,
+                reserve_price,
+                denom,
+                1,
+                vec![],
+                vec![],
+                None,
+                SaleType::SealedBidAuction(auction),
+            )
+        }
+        ExecuteMsg::CreateServiceListing {
+            listing_title,
+            external_id,
+            text,
+            tags,
+            contact,
+            denom,
+            quantity,
+            milestones,
+        } => {
+            let service = build_service(&CONFIG.load(deps.storage)?, milestones)?;
+            let price = service.milestones.iter().map(|terms| terms.amount).sum();
+            execute_create_listing(
+                deps,
+                env,
+                info,
+                listing_title,
+                external_id,
+                text,
+                tags,
+                contact,
+                price,
+                denom,
+                quantity,
+                vec![],
+                vec![],
+                None,
+                SaleType::Service(service),
+            )
+        }
         ExecuteMsg::EditListing {
             listing_id,
             external_id,
             text,
             tags,
             price,
-        } => execute_edit_listing(deps, env, info, listing_id, external_id, text, tags, price),
+            variants,
+            shipping_options,
+            ship_within,
+        } => execute_edit_listing(
+            deps,
+            env,
+            info,
+            listing_id,
+            external_id,
+            text,
+            tags,
+            price,
+            variants,
+            shipping_options,
+            ship_within,
+        ),
         ExecuteMsg::DeleteListing { listing_id } => {
             execute_delete_listing(deps, env, info, listing_id)
         }
-        ExecuteMsg::Purchase { listing_id } => execute_purchase(deps, env, info, listing_id),
-        ExecuteMsg::CancelPurchase { listing_id } => {
-            execute_cancel_purchase(deps, env, info, listing_id)
+        ExecuteMsg::RestockListing {
+            listing_id,
+            quantity,
+            variant_id,
+        } => execute_restock_listing(deps, env, info, listing_id, quantity, variant_id),
+        ExecuteMsg::Purchase {
+            listing_id,
+            variant_id,
+            shipping_id,
+        } => execute_purchase(deps, env, info, listing_id, variant_id, shipping_id),
+        ExecuteMsg::PurchaseMany {
+            listing_ids,
+            group_by_seller,
+            region,
+        } => execute_purchase_many(deps, env, info, listing_ids, group_by_seller, region),
+        ExecuteMsg::PlaceBid { listing_id } => execute_place_bid(deps, env, info, listing_id),
+        ExecuteMsg::CommitBid {
+            listing_id,
+            commitment,
+        } => execute_commit_bid(deps, env, info, listing_id, commitment),
+        ExecuteMsg::RevealBid {
+            listing_id,
+            amount,
+            salt,
+        } => execute_reveal_bid(deps, env, info, listing_id, amount, salt),
+        ExecuteMsg::ReclaimDeposit { listing_id } => {
+            execute_reclaim_deposit(deps, env, info, listing_id)
+        }
+        ExecuteMsg::SettleAuction { listing_id } => execute_settle_auction(deps, env, listing_id),
+        ExecuteMsg::MakeOffer {
+            listing_id,
+            variant_id,
+            shipping_id,
+            price,
+            expires_at,
+        } => execute_make_offer(
+            deps,
+            env,
+            info,
+            listing_id,
+            variant_id,
+            shipping_id,
+            price,
+            expires_at,
+        ),
+        ExecuteMsg::AcceptOffer { offer_id } => execute_accept_offer(deps, env, info, offer_id),
+        ExecuteMsg::RejectOffer { offer_id } => execute_reject_offer(deps, env, info, offer_id),
+        ExecuteMsg::CounterOffer { offer_id, price } => {
+            execute_counter_offer(deps, env, info, offer_id, price)
+        }
+        ExecuteMsg::WithdrawOffer { offer_id } => execute_withdraw_offer(deps, env, info, offer_id),
+        ExecuteMsg::PostWantedAd {
+            title,
+            text,
+            tags,
+            denom,
+            budget,
+        } => {
+            //cw20 budgets are escrowed by sending tokens through Receive
+            if cw20_contract(&denom).is_some() {
+                return Err(ContractError::InvalidDenom { denom });
+            }
+            let payment = check_payment(&info.funds, &denom, budget as u128)?;
+            save_wanted_ad(deps, &env, &info.sender, title, text, tags, budget, payment)
+        }
+        ExecuteMsg::ProposeFulfilment {
+            wanted_ad_id,
+            price,
+            text,
+            external_id,
+            contact,
+        } => execute_propose_fulfilment(
+            deps,
+            env,
+            info,
+            wanted_ad_id,
+            price,
+            text,
+            external_id,
+            contact,
+        ),
+        ExecuteMsg::AcceptProposal {
+            wanted_ad_id,
+            proposal_id,
+        } => execute_accept_proposal(deps, env, info, wanted_ad_id, proposal_id),
+        ExecuteMsg::CancelWantedAd { wanted_ad_id } => {
+            execute_cancel_wanted_ad(deps, env, info, wanted_ad_id)
         }
-        ExecuteMsg::SignShipped { listing_id } => execute_sign_shipped(deps, env, info, listing_id),
-        ExecuteMsg::SignReceived { listing_id } => {
-            execute_sign_received(deps, env, info, listing_id)
+        ExecuteMsg::CancelPurchase { order_id } => {
+            execute_cancel_purchase(deps, env, info, order_id)
         }
-        ExecuteMsg::RequestArbitration { listing_id } => {
-            execute_request_arbitration(deps, env, info, listing_id)
+        ExecuteMsg::SignShipped { order_id } => execute_sign_shipped(deps, env, info, order_id),
+        ExecuteMsg::SignReceived { order_id } => execute_sign_received(deps, env, info, order_id),
+        ExecuteMsg::ExpireUnshipped { order_id } => execute_expire_unshipped(deps, env, order_id),
+        ExecuteMsg::ClaimAfterTimeout { order_id } => {
+            execute_claim_after_timeout(deps, env, order_id)
         }
+        ExecuteMsg::DeliverMilestone {
+            order_id,
+            milestone_id,
+        } => execute_deliver_milestone(deps, env, info, order_id, milestone_id),
+        ExecuteMsg::ApproveMilestone {
+            order_id,
+            milestone_id,
+        } => execute_approve_milestone(deps, env, info, order_id, milestone_id),
+        ExecuteMsg::RequestArbitration {
+            order_id,
+            milestone_id,
+        } => execute_request_arbitration(deps, env, info, order_id, milestone_id),
+        ExecuteMsg::ProposeSettlement {
+            order_id,
+            milestone_id,
+            buyer_share_bps,
+        } => execute_propose_settlement(deps, env, info, order_id, milestone_id, buyer_share_bps),
+        ExecuteMsg::AcceptSettlement {
+            order_id,
+            milestone_id,
+            buyer_share_bps,
+        } => execute_accept_settlement(deps, env, info, order_id, milestone_id, buyer_share_bps),
+        ExecuteMsg::RevokeSettlement {
+            order_id,
+            milestone_id,
+        } => execute_revoke_settlement(deps, info, order_id, milestone_id),
         ExecuteMsg::Arbitrate {
-            listing_id,
-            funds_recipient,
-        } => execute_arbitrate(deps, env, info, listing_id, funds_recipient),
+            order_id,
+            ruling,
+            milestone_id,
+        } => execute_arbitrate(deps, env, info, order_id, ruling, milestone_id),
         ExecuteMsg::CreateProfile { profile_name } => {
             execute_create_profile(deps, env, info, profile_name)
         }
         ExecuteMsg::DeleteProfile {} => execute_delete_profile(deps, env, info),
-        ExecuteMsg::SellerCancelSale { listing_id } => {
-            execute_seller_cancel_sale(deps, env, info, listing_id)
+        ExecuteMsg::SellerCancelSale { order_id } => {
+            execute_seller_cancel_sale(deps, env, info, order_id)
         }
         ExecuteMsg::RateUser { recipient_address, rating } => {
             execute_rate_user(deps, env, info, recipient_address, rating)
         }
         ExecuteMsg::CleanupOldRelationships {} => execute_cleanup_old_relationships(deps, env),
+        ExecuteMsg::UpdateConfig {
+            fee_bps,
+            fee_recipients,
+            accepted_denoms,
+            gateway,
+            max_text_length,
+            max_id_length,
+            push_payouts,
+            confirmation_window,
+            ship_window,
+            max_arbiter_fee_bps,
+        } => execute_update_config(
+            deps,
+            env,
+            info,
+            fee_bps,
+            fee_recipients,
+            accepted_denoms,
+            gateway,
+            max_text_length,
+            max_id_length,
+            push_payouts,
+            confirmation_window,
+            ship_window,
+            max_arbiter_fee_bps,
+        ),
+        ExecuteMsg::ProposeNewAdmin { new_admin } => {
+            execute_propose_new_admin(deps, env, info, new_admin)
+        }
+        ExecuteMsg::AcceptAdmin {} => execute_accept_admin(deps, env, info),
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Withdraw { denom, amount } => execute_withdraw(deps, env, info, denom, amount),
+        ExecuteMsg::SweepExcess { denom, recipient } => {
+            execute_sweep_excess(deps, env, info, denom, recipient)
+        }
+        ExecuteMsg::AddArbiter {
+            address,
+            name,
+            fee_bps,
+        } => execute_add_arbiter(deps, info, address, name, fee_bps),
+        ExecuteMsg::RemoveArbiter { address } => execute_remove_arbiter(deps, info, address),
+    }
+}
+
+//cw20 denoms must point at a valid token contract address
+fn validate_denoms(api: &dyn Api, denoms: &[String]) -> StdResult<()> {
+    for denom in denoms {
+        if let Some(token_contract) = cw20_contract(denom) {
+            api.addr_validate(token_contract)?;
+        }
     }
+    Ok(())
 }
 pub fn execute_create_profile(
     deps: DepsMut,
@@ -123,6 +525,7 @@ pub fn execute_create_profile(
         ratings: 0,
         rating_count: 0,
         average_rating: 0,
+        failed_shipments: 0,
     };
     PROFILES.save(deps.storage, info.sender.clone(), &profile)?;
     PROFILE_NAME.save(deps.storage, info.sender.clone(), &profile_name)?;
@@ -142,15 +545,31 @@ fn execute_create_listing(
     tags: Vec<String>,
     contact: String,
     price: u64,
+    denom: String,
+    quantity: u64,
+    variants: Vec<VariantMsg>,
+    shipping_options: Vec<ShippingOptionMsg>,
+    ship_within: Option<u64>,
+    sale_type: SaleType,
 ) -> Result<Response, ContractError> {
-    if text.len() > MAX_TEXT_LENGTH {
-        return Err(ContractError::TooMuchText {});
+    let config = CONFIG.load(deps.storage)?;
+    if !config.accepted_denoms.contains(&denom) {
+        return Err(ContractError::InvalidDenom { denom });
     }
-    if external_id.len() > MAX_ID_LENGTH {
-        return Err(ContractError::OnlyOneLink {});
+    validate_title(&config, &listing_title)?;
+    validate_contact(&config, &contact)?;
+    if let Some(ship_within) = ship_within {
+        validate_window("ship_within", ship_within)?;
+    }
+    if text.len() as u64 > config.max_text_length {
+        return Err(ContractError::TooMuchText {});
     }
-    if is_false(external_id.starts_with(IPFS)) {
-        return Err(ContractError::MustUseJulianGateway {});
+    validate_external_id(&config, &external_id)?;
+    let variants = build_variants(&config, variants)?;
+    let shipping_options = build_shipping_options(&config, shipping_options)?;
+    let (price, quantity) = variant_totals(&variants).unwrap_or((price, quantity));
+    if quantity == 0 {
+        return Err(ContractError::InvalidQuantity {});
     }
     //load article count from state and increment
     let counter = LISTING_COUNT.load(deps.storage)?;
@@ -167,11 +586,14 @@ fn execute_create_listing(
         seller: info.sender.to_string(),
         contact,
         price,
-        buyer: None,
-        bought: false,
-        shipped: false,
-        received: false,
-        arbitration_requested: false,
+        denom,
+        status: ListingStatus::Active,
+        quantity,
+        reserved: 0,
+        variants,
+        shipping_options,
+        ship_within,
+        sale_type,
         creation_date: env.block.time.to_string(),
         last_edit_date: None,
     };
@@ -195,25 +617,85 @@ fn execute_edit_listing(
     text: String,
     tags: Vec<String>,
     price: u64,
+    variants: Option<Vec<VariantMsg>>,
+    shipping_options: Option<Vec<ShippingOptionMsg>>,
+    ship_within: Option<u64>,
 ) -> Result<Response, ContractError> {
-    if text.len() > MAX_TEXT_LENGTH {
+    let config = CONFIG.load(deps.storage)?;
+    if text.len() as u64 > config.max_text_length {
         return Err(ContractError::TooMuchText {});
     }
-    if external_id.len() > MAX_ID_LENGTH {
-        return Err(ContractError::OnlyOneLink {});
-    }
-    if is_false(external_id.starts_with(IPFS)) {
-        return Err(ContractError::MustUseJulianGateway {});
-    }
+    validate_external_id(&config, &external_id)?;
     //load post by ID passed
-    let listing = LISTING.load(deps.storage, listing_id)?;
+    let mut listing = LISTING.load(deps.storage, listing_id)?;
     //make sure editor is seller
     if info.sender.to_string() != listing.seller {
         return Err(ContractError::Unauthorized {});
     }
-    //Prevent editing a listing that has been purchased (fraud protection)
-    if listing.bought {
-        return Err(ContractError::AlreadyPurchased {});
+    //Prevent editing a listing while buyers have paid for it (fraud protection)
+    if listing.reserved > 0 {
+        return Err(ContractError::OpenOrders {
+            listing_id,
+            reserved: listing.reserved,
+        });
+    }
+    if (variants.is_some() || shipping_options.is_some())
+        && listing.sale_type != SaleType::FixedPrice
+    {
+        return Err(ContractError::WrongSaleType {
+            listing_id,
+            sale_type: listing.sale_type.to_string(),
+        });
+    }
+    match &mut listing.sale_type {
+        SaleType::FixedPrice => {}
+        //an auction can be reworded until the first bid, after that bidders are owed what they bid on
+        SaleType::EnglishAuction(auction) => {
+            if auction.highest_bid.is_some() {
+                return Err(ContractError::AuctionHasBids { listing_id });
+            }
+            auction.start_price = price;
+        }
+        SaleType::DutchAuction(auction) => {
+            if price <= auction.floor_price {
+                return Err(ContractError::InvalidPriceSchedule {});
+            }
+            auction.start_price = price;
+        }
+        SaleType::SealedBidAuction(auction) => {
+            if auction.commitment_count > 0 {
+                return Err(ContractError::AuctionHasBids { listing_id });
+            }
+            auction.reserve_price = price;
+        }
+        //a service is priced by its milestones
+        SaleType::Service(_) => {
+            if price != listing.price {
+                return Err(ContractError::InvalidMilestones {});
+            }
+        }
+    }
+    let (price, quantity) = match variants {
+        Some(variants) => {
+            listing.variants = build_variants(&config, variants)?;
+            let (price, quantity) = variant_totals(&listing.variants).unwrap_or_default();
+            if quantity == 0 {
+                return Err(ContractError::InvalidQuantity {});
+            }
+            //new stock reopens a sold out listing
+            if listing.status == ListingStatus::SoldOut {
+                transition(&mut listing, ListingStatus::Active)?;
+            }
+            (price, quantity)
+        }
+        None => variant_totals(&listing.variants).unwrap_or((price, listing.quantity)),
+    };
+    if let Some(shipping_options) = shipping_options {
+        listing.shipping_options = build_shipping_options(&config, shipping_options)?;
+    }
+    if let Some(ship_within) = ship_within {
+        validate_window("ship_within", ship_within)?;
+        listing.ship_within = Some(ship_within);
     }
     //update post content
     let new_post: Listing = Listing {
@@ -221,15 +703,18 @@ fn execute_edit_listing(
         listing_title: listing.listing_title,
         external_id,
         price,
+        denom: listing.denom,
         text,
         tags,
         seller: listing.seller,
         contact: listing.contact,
-        bought: listing.bought,
-        buyer: listing.buyer,
-        shipped: listing.shipped,
-        received: listing.received,
-        arbitration_requested: listing.arbitration_requested,
+        status: listing.status,
+        quantity,
+        reserved: listing.reserved,
+        variants: listing.variants,
+        shipping_options: listing.shipping_options,
+        ship_within: listing.ship_within,
+        sale_type: listing.sale_type,
         creation_date: listing.creation_date,
         last_edit_date: Some(env.block.time.to_string()),
     };
@@ -240,17 +725,36 @@ fn execute_edit_listing(
         .add_attribute("post_id", new_post.listing_id.to_string()))
 }
 fn execute_delete_listing(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
     listing_id: u64,
 ) -> Result<Response, ContractError> {
+    let mut listing = LISTING.load(deps.storage, listing_id)?;
     //Ensure the sender is the seller
-    if info.sender.to_string() != LISTING.load(deps.storage, listing_id)?.seller {
+    if info.sender.to_string() != listing.seller {
         return Err(ContractError::Unauthorized {});
     }
+    //a listing buyers have paid for can only be closed out through the escrow flow
+    if listing.reserved > 0 {
+        return Err(ContractError::OpenOrders {
+            listing_id,
+            reserved: listing.reserved,
+        });
+    }
+    let has_bids = match &listing.sale_type {
+        SaleType::EnglishAuction(auction) => {
+            auction.highest_bid.is_some() && listing.status == ListingStatus::Active
+        }
+        //deposits are reclaimed through the listing, so it stays until every one is returned
+        SaleType::SealedBidAuction(auction) => auction.deposits_held > 0,
+        _ => false,
+    };
+    if has_bids {
+        return Err(ContractError::AuctionHasBids { listing_id });
+    }
+    transition(&mut listing, ListingStatus::Cancelled)?;
     //remove listing title mapping from state
-    let listing = LISTING.load(deps.storage, listing_id)?;
     LISTING_TITLES.remove(deps.storage, listing.listing_title);
     //remove post from state via post id
     LISTING.remove(deps.storage, listing_id);
@@ -259,210 +763,2623 @@ fn execute_delete_listing(
     let updated_counter = counter - 1;
     //save decremented counter
     LISTING_COUNT.save(deps.storage, &updated_counter)?;
+    let refund_msgs = reject_open_offers(&mut deps, &env, listing_id)?;
     Ok(Response::new()
+        .add_messages(refund_msgs)
         .add_attribute("action", "delete_post")
         .add_attribute("post_id", listing_id.to_string()))
 }
 
-//reusable function to check if an address is a harcoded arbiter (for state effective multisig thingy)
-fn is_arbiter(deps: &DepsMut, sender: &str) -> bool {
-    ARBITERS.iter().any(|&arbiter| {
-        let validated_arbiter = deps.api.addr_validate(arbiter).unwrap();
-        deps.api.addr_validate(sender).unwrap() == validated_arbiter
-    })
+fn execute_restock_listing(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+    quantity: u64,
+    variant_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut listing = LISTING.load(deps.storage, listing_id)?;
+    if info.sender.to_string() != listing.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    if quantity == 0 {
+        return Err(ContractError::InvalidQuantity {});
+    }
+    if listing.sale_type.takes_bids() {
+        return Err(ContractError::WrongSaleType {
+            listing_id,
+            sale_type: listing.sale_type.to_string(),
+        });
+    }
+    if let Some(index) = variant_index(&listing, variant_id)? {
+        listing.variants[index].quantity += quantity;
+    }
+    listing.quantity += quantity;
+    if listing.status == ListingStatus::SoldOut {
+        transition(&mut listing, ListingStatus::Active)?;
+    }
+    LISTING.save(deps.storage, listing_id, &listing)?;
+    Ok(Response::new()
+        .add_attribute("action", "restock_listing")
+        .add_attribute("listing_id", listing_id.to_string())
+        .add_attribute("quantity", listing.quantity.to_string())
+        .add_attribute("status", listing.status.to_string()))
+}
+
+//a zero length window would close the moment it opens
+fn validate_window(window: &str, seconds: u64) -> Result<(), ContractError> {
+    if seconds == 0 {
+        return Err(ContractError::InvalidWindow {
+            window: window.to_string(),
+        });
+    }
+    Ok(())
+}
+
+//listing and wanted ad titles can't be blank or longer than an id
+fn validate_title(config: &Config, title: &str) -> Result<(), ContractError> {
+    if title.trim().is_empty() || title.len() as u64 > config.max_id_length {
+        return Err(ContractError::InvalidTitle {
+            max_length: config.max_id_length,
+        });
+    }
+    Ok(())
+}
+
+//how to reach a seller, on a listing or a fulfilment proposal, is held to the same bounds as a title
+fn validate_contact(config: &Config, contact: &str) -> Result<(), ContractError> {
+    if contact.trim().is_empty() || contact.len() as u64 > config.max_id_length {
+        return Err(ContractError::InvalidContact {
+            max_length: config.max_id_length,
+        });
+    }
+    Ok(())
+}
+
+//external ids (listing and variant images) must be a single link through the configured gateway
+fn validate_external_id(config: &Config, external_id: &str) -> Result<(), ContractError> {
+    if external_id.len() as u64 > config.max_id_length {
+        return Err(ContractError::OnlyOneLink {});
+    }
+    if is_false(external_id.starts_with(&config.gateway)) {
+        return Err(ContractError::MustUseJulianGateway {
+            gateway: config.gateway.clone(),
+        });
+    }
+    Ok(())
+}
+
+fn build_variants(
+    config: &Config,
+    variants: Vec<VariantMsg>,
+) -> Result<Vec<Variant>, ContractError> {
+    variants
+        .into_iter()
+        .zip(1..)
+        .map(|(variant, variant_id)| {
+            if variant.name.len() as u64 > config.max_text_length {
+                return Err(ContractError::TooMuchText {});
+            }
+            if let Some(external_id) = &variant.external_id {
+                validate_external_id(config, external_id)?;
+            }
+            Ok(Variant {
+                variant_id,
+                name: variant.name,
+                price: variant.price,
+                quantity: variant.quantity,
+                reserved: 0,
+                external_id: variant.external_id,
+            })
+        })
+        .collect()
+}
+
+//the listing's price and quantity when it has variants: the lowest price and the total stock
+fn variant_totals(variants: &[Variant]) -> Option<(u64, u64)> {
+    let price = variants.iter().map(|variant| variant.price).min()?;
+    let quantity = variants.iter().map(|variant| variant.quantity).sum();
+    Some((price, quantity))
+}
+
+fn build_shipping_options(
+    config: &Config,
+    shipping_options: Vec<ShippingOptionMsg>,
+) -> Result<Vec<ShippingOption>, ContractError> {
+    shipping_options
+        .into_iter()
+        .zip(1..)
+        .map(|(option, shipping_id)| {
+            let text_length =
+                option.regions.iter().map(String::len).sum::<usize>() + option.name.len();
+            if text_length as u64 > config.max_text_length {
+                return Err(ContractError::TooMuchText {});
+            }
+            Ok(ShippingOption {
+                shipping_id,
+                name: option.name,
+                price: option.price,
+                regions: option.regions,
+                estimated_days: option.estimated_days,
+            })
+        })
+        .collect()
+}
+
+fn build_service(config: &Config, milestones: Vec<MilestoneMsg>) -> Result<Service, ContractError> {
+    if milestones.is_empty() || milestones.iter().any(|milestone| milestone.amount == 0) {
+        return Err(ContractError::InvalidMilestones {});
+    }
+    let milestones = milestones
+        .into_iter()
+        .map(|milestone| {
+            if milestone.description.len() as u64 > config.max_text_length {
+                return Err(ContractError::TooMuchText {});
+            }
+            Ok(MilestoneTerms {
+                description: milestone.description,
+                amount: milestone.amount,
+            })
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(Service { milestones })
+}
+
+//the shipping option picked at purchase, listings with shipping options can only be bought with one
+fn shipping_option(
+    listing: &Listing,
+    shipping_id: Option<u64>,
+) -> Result<Option<ShippingOption>, ContractError> {
+    match shipping_id {
+        None if listing.shipping_options.is_empty() => Ok(None),
+        None => Err(ContractError::ShippingRequired {
+            listing_id: listing.listing_id,
+        }),
+        Some(shipping_id) => listing
+            .shipping_options
+            .iter()
+            .find(|option| option.shipping_id == shipping_id)
+            .cloned()
+            .map(Some)
+            .ok_or(ContractError::InvalidShipping {
+                listing_id: listing.listing_id,
+                shipping_id,
+            }),
+    }
+}
+
+//what the buyer pays for a unit, shipping included
+fn with_shipping(price: u64, shipping: Option<&ShippingOption>) -> u64 {
+    price + shipping.map_or(0, |shipping| shipping.price)
+}
+
+//the cheapest shipping option covering a region, for carts where the buyer can't pick per listing
+fn shipping_to_region(
+    listing: &Listing,
+    region: Option<&str>,
+) -> Result<Option<ShippingOption>, ContractError> {
+    if listing.shipping_options.is_empty() {
+        return Ok(None);
+    }
+    let Some(region) = region else {
+        return Err(ContractError::ShippingRequired {
+            listing_id: listing.listing_id,
+        });
+    };
+    listing
+        .shipping_options
+        .iter()
+        .filter(|option| option.regions.iter().any(|tag| tag == region))
+        .min_by_key(|option| option.price)
+        .cloned()
+        .map(Some)
+        .ok_or(ContractError::NoShippingToRegion {
+            listing_id: listing.listing_id,
+            region: region.to_string(),
+        })
+}
+
+//position of the variant being bought, listings with variants can only be bought through one
+fn variant_index(
+    listing: &Listing,
+    variant_id: Option<u64>,
+) -> Result<Option<usize>, ContractError> {
+    match variant_id {
+        None if listing.variants.is_empty() => Ok(None),
+        None => Err(ContractError::VariantRequired {
+            listing_id: listing.listing_id,
+        }),
+        Some(variant_id) => listing
+            .variants
+            .iter()
+            .position(|variant| variant.variant_id == variant_id)
+            .map(Some)
+            .ok_or(ContractError::InvalidVariant {
+                listing_id: listing.listing_id,
+                variant_id,
+            }),
+    }
+}
+
+//what one unit costs, the variant's own price when buying a variant
+fn unit_price(
+    listing: &Listing,
+    variant_id: Option<u64>,
+    now: Timestamp,
+) -> Result<u64, ContractError> {
+    match variant_index(listing, variant_id)? {
+        Some(index) => {
+            let variant = &listing.variants[index];
+            if variant.quantity == 0 {
+                return Err(ContractError::VariantSoldOut {
+                    listing_id: listing.listing_id,
+                    variant_id: variant.variant_id,
+                });
+            }
+            Ok(variant.price)
+        }
+        None => Ok(current_price(listing, now)),
+    }
+}
+
+//Settlement payouts go through here. In push mode the recipient is sent the funds directly, otherwise
+//they are credited to BALANCES so a recipient that cannot receive funds can't block settlement.
+fn payout(
+    deps: &mut DepsMut,
+    config: &Config,
+    recipient: &str,
+    amount: u128,
+    denom: &str,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    if amount == 0 {
+        return Ok(vec![]);
+    }
+    if config.push_payouts {
+        return Ok(vec![send_tokens(recipient, amount, denom)?]);
+    }
+    let recipient = deps.api.addr_validate(recipient)?;
+    BALANCES.update(
+        deps.storage,
+        (recipient, denom.to_string()),
+        |balance| -> StdResult<_> { Ok(balance.unwrap_or_default() + Uint128::new(amount)) },
+    )?;
+    add_to_total(deps.storage, &OWED, denom, amount)?;
+    Ok(vec![])
+}
+
+//keeps the ESCROWED and OWED per denom totals in step with purchases, settlements and withdrawals
+fn add_to_total(
+    storage: &mut dyn Storage,
+    totals: &Map<String, Uint128>,
+    denom: &str,
+    amount: u128,
+) -> StdResult<()> {
+    totals.update(storage, denom.to_string(), |total| -> StdResult<_> {
+        Ok(total.unwrap_or_default() + Uint128::new(amount))
+    })?;
+    Ok(())
+}
+
+//an order's price and shipping are held in escrow from purchase until it is settled or refunded
+fn escrow_order(storage: &mut dyn Storage, order: &Order) -> StdResult<()> {
+    add_to_total(storage, &ESCROWED, &order.denom, order.total().into())
+}
+
+fn release_escrow(storage: &mut dyn Storage, order: &Order) -> StdResult<()> {
+    subtract_from_total(storage, &ESCROWED, &order.denom, order.escrowed().into())
+}
+
+fn subtract_from_total(
+    storage: &mut dyn Storage,
+    totals: &Map<String, Uint128>,
+    denom: &str,
+    amount: u128,
+) -> StdResult<()> {
+    let total = totals
+        .may_load(storage, denom.to_string())?
+        .unwrap_or_default()
+        .checked_sub(Uint128::new(amount))?;
+    if total.is_zero() {
+        totals.remove(storage, denom.to_string());
+    } else {
+        totals.save(storage, denom.to_string(), &total)?;
+    }
+    Ok(())
+}
+
+//every listing status change goes through here so impossible states are rejected in one place
+fn transition(listing: &mut Listing, to: ListingStatus) -> Result<(), ContractError> {
+    if !listing.status.can_transition_to(to) {
+        return Err(ContractError::InvalidStatusTransition {
+            listing_id: listing.listing_id,
+            from: listing.status,
+            to,
+        });
+    }
+    listing.status = to;
+    Ok(())
+}
+
+//same for orders, which also record when they reached each state
+fn transition_order(
+    order: &mut Order,
+    to: OrderStatus,
+    now: Timestamp,
+) -> Result<(), ContractError> {
+    if !order.status.can_transition_to(to) {
+        return Err(ContractError::InvalidOrderTransition {
+            order_id: order.order_id,
+            from: order.status,
+            to,
+        });
+    }
+    order.status = to;
+    match to {
+        OrderStatus::Purchased => {}
+        OrderStatus::Shipped => order.shipped_at = Some(now),
+        OrderStatus::Disputed => order.disputed_at = Some(now),
+        OrderStatus::Completed => order.completed_at = Some(now),
+        OrderStatus::Refunded => order.refunded_at = Some(now),
+    }
+    Ok(())
+}
+
+//takes one unit out of stock for a new order, selling out the listing on the last one
+fn reserve_unit(listing: &mut Listing, variant_id: Option<u64>) -> Result<(), ContractError> {
+    if let Some(index) = variant_index(listing, variant_id)? {
+        listing.variants[index].quantity -= 1;
+        listing.variants[index].reserved += 1;
+    }
+    listing.quantity -= 1;
+    listing.reserved += 1;
+    if listing.quantity == 0 {
+        transition(listing, ListingStatus::SoldOut)?;
+    }
+    Ok(())
+}
+
+//frees the unit held by a closed order, putting it back in stock when the seller still has it
+fn release_unit(
+    storage: &mut dyn Storage,
+    order: &Order,
+    restock: bool,
+) -> Result<(), ContractError> {
+    //wanted ad orders have no stock to release
+    let Some(listing_id) = order.listing_id else {
+        return Ok(());
+    };
+    let mut listing = LISTING.load(storage, listing_id)?;
+    //a refunded auction win doesn't reopen bidding on an auction that has ended
+    let restock = restock && !listing.sale_type.takes_bids();
+    if let Some(index) = variant_index(&listing, order.variant_id)? {
+        listing.variants[index].reserved -= 1;
+        if restock {
+            listing.variants[index].quantity += 1;
+        }
+    }
+    listing.reserved -= 1;
+    if restock {
+        listing.quantity += 1;
+        if listing.status == ListingStatus::SoldOut {
+            transition(&mut listing, ListingStatus::Active)?;
+        }
+    }
+    LISTING.save(storage, listing_id, &listing)?;
+    Ok(())
+}
+
+//listing price, or where a dutch auction's schedule has it at `now`
+fn current_price(listing: &Listing, now: Timestamp) -> u64 {
+    match &listing.sale_type {
+        SaleType::DutchAuction(auction) => auction.price_at(now),
+        _ => listing.price,
+    }
+}
+
+//reusable function to check if an address is an active arbiter in the registry
+fn is_arbiter(storage: &dyn Storage, sender: &Addr) -> StdResult<bool> {
+    Ok(ARBITERS
+        .may_load(storage, sender.clone())?
+        .is_some_and(|arbiter| arbiter.active))
 }
 
 fn execute_sign_shipped(
     deps: DepsMut,
     env: Env,
     info: MessageInfo,
-    listing_id: u64,
+    order_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut listing = LISTING.load(deps.storage, listing_id)?;
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    let config = CONFIG.load(deps.storage)?;
     
-    if info.sender.to_string() != listing.seller && !is_arbiter(&deps, info.sender.as_ref()) {
+    if info.sender != order.seller && !is_arbiter(deps.storage, &info.sender)? {
         return Err(ContractError::Unauthorized {});
     }
+    if !order.milestones.is_empty() {
+        return Err(ContractError::ServiceOrder { order_id });
+    }
     
-    listing.shipped = true;
+    transition_order(&mut order, OrderStatus::Shipped, env.block.time)?;
+    let confirm_by = env.block.time.plus_seconds(config.confirmation_window);
+    order.confirm_by = Some(confirm_by);
+    let mut shipped = vec![order_id.to_string()];
+    //the rest of a shared shipment ships with it, skipping orders cancelled since purchase
+    if let Some(shipment_id) = order.shipment_id {
+        let order_ids: Vec<u64> = SHIPMENTS
+            .prefix(shipment_id)
+            .keys(deps.storage, None, None, StorageOrder::Ascending)
+            .collect::<StdResult<_>>()?;
+        for other_id in order_ids
+            .into_iter()
+            .filter(|other_id| *other_id != order_id)
+        {
+            let mut other = ORDERS.load(deps.storage, other_id)?;
+            if other.status == OrderStatus::Purchased {
+                transition_order(&mut other, OrderStatus::Shipped, env.block.time)?;
+                other.confirm_by = Some(confirm_by);
+                ORDERS.save(deps.storage, other_id, &other)?;
+                shipped.push(other_id.to_string());
+            }
+        }
+    }
     
     // Create relationship record with timestamp in seconds
     let relationship = Relationship {
-        seller: listing.seller.clone(),
-        buyer: listing.buyer.clone().unwrap(),
+        seller: order.seller.to_string(),
+        buyer: order.buyer.to_string(),
         sell_date: env.block.time.seconds().to_string(),
     };
     
     // Create a unique key for the relationship
-    let relationship_key = format!("{}:{}", listing.seller, listing.buyer.clone().unwrap());
+    let relationship_key = format!("{}:{}", order.seller, order.buyer);
     RELATIONSHIPS.save(deps.storage, relationship_key.clone(), &relationship)?;
     
-    LISTING.save(deps.storage, listing_id, &listing)?;
+    ORDERS.save(deps.storage, order_id, &order)?;
     
     Ok(Response::new()
         .add_attribute("action", "sign_shipped")
-        .add_attribute("listing_id", listing_id.to_string())
+        .add_attribute("order_id", order_id.to_string())
+        .add_attributes([order_source(&order)])
+        .add_attribute("status", order.status.to_string())
+        .add_attribute("shipped_orders", shipped.join(","))
+        .add_attribute("confirm_by", confirm_by.seconds().to_string())
         .add_attribute("relationship_created", relationship_key))
 }
-//When the buyer receives the item, the seller is paid 95%, the ADMIN is paid 5%, and the listing is deleted.
+//When the buyer receives the item, the seller is paid the price minus the configured fee plus the shipping, the fee is split between the fee recipients, and the order is completed.
 fn execute_sign_received(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    listing_id: u64,
+    order_id: u64,
 ) -> Result<Response, ContractError> {
-    let listing = LISTING.load(deps.storage, listing_id)?;
-    //check if post shipped value is false
-    if !listing.shipped {
-        return Err(ContractError::NotShipped {});
-    }
+    let mut order = ORDERS.load(deps.storage, order_id)?;
     // Verify the executor is the buyer
-    if Some(info.sender.to_string()) != listing.buyer {
+    if info.sender != order.buyer {
         return Err(ContractError::Unauthorized {});
     }
-
-    // Calculate 5% fee
-    let fee_amount = listing.price as u128 * 5 / 100;
-    let seller_amount = listing.price as u128 - fee_amount;
-
-    // Create bank messages for both seller and admin
-    let seller_msg = BankMsg::Send {
-        to_address: listing.seller.to_string(),
-        amount: vec![coin(seller_amount, ATOM)],
-    };
-
-    let admin_msg = BankMsg::Send {
-        to_address: ADMIN.to_string(),
-        amount: vec![coin(fee_amount, ATOM)],
-    };
-
-    // Update transaction counts for both buyer and seller
-    let seller_addr = deps.api.addr_validate(&listing.seller)?;
-    let buyer_addr = deps.api.addr_validate(&listing.buyer.clone().unwrap())?;
-    
-    // Only update profiles if they exist
-    if let Ok(mut seller_profile) = PROFILES.load(deps.storage, seller_addr.clone()) {
-        seller_profile.transaction_count += 1;
-        PROFILES.save(deps.storage, seller_addr, &seller_profile)?;
-    }
-    
-    if let Ok(mut buyer_profile) = PROFILES.load(deps.storage, buyer_addr.clone()) {
-        buyer_profile.transaction_count += 1;
-        PROFILES.save(deps.storage, buyer_addr, &buyer_profile)?;
+    if !order.milestones.is_empty() {
+        return Err(ContractError::ServiceOrder { order_id });
     }
-
-    LISTING.remove(deps.storage, listing_id);
-    let resp = Response::new()
+    //seller must sign shipped prior to buyer signing received
+    let (payout_msgs, payout_attributes) = complete_order(&mut deps, &env, &mut order)?;
+    Ok(Response::new()
         .add_attribute("action", "sign_received")
-        .add_message(seller_msg)
-        .add_message(admin_msg)
+        .add_attribute("order_id", order_id.to_string())
+        .add_attribute("status", order.status.to_string())
+        .add_messages(payout_msgs)
         .add_attribute("action", "release funds to seller")
-        .add_attribute("amount to seller", seller_amount.to_string())
-        .add_attribute("fee to admin", fee_amount.to_string());
-    Ok(resp)
+        .add_attributes(payout_attributes))
 }
 
-fn execute_request_arbitration(
-    deps: DepsMut,
-    _env: Env,
-    info: MessageInfo,
-    listing_id: u64,
+//Releases a shipped order the buyer never confirmed or disputed once its confirmation window is over
+fn execute_claim_after_timeout(
+    mut deps: DepsMut,
+    env: Env,
+    order_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut listing = LISTING.load(deps.storage, listing_id)?;
-    if !listing.shipped || !listing.bought {
-        return Err(ContractError::NotEligibleForArbitration {});
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    if !order.milestones.is_empty() {
+        return Err(ContractError::ServiceOrder { order_id });
+    }
+    //a disputed order is left to the arbiter
+    if order.status != OrderStatus::Shipped {
+        return Err(ContractError::InvalidOrderTransition {
+            order_id,
+            from: order.status,
+            to: OrderStatus::Completed,
+        });
     }
-    // Only allow buyer or post creator to request arbitration
-    if info.sender.to_string() != listing.seller && Some(info.sender.to_string()) != listing.buyer {
+    //orders shipped before confirmation windows existed still need the buyer to sign
+    let confirm_by = order
+        .confirm_by
+        .ok_or(ContractError::NoConfirmationWindow { order_id })?;
+    if env.block.time < confirm_by {
+        return Err(ContractError::ConfirmationWindowOpen {
+            order_id,
+            confirm_by,
+        });
+    }
+    let (payout_msgs, payout_attributes) = complete_order(&mut deps, &env, &mut order)?;
+    Ok(Response::new()
+        .add_attribute("action", "claim_after_timeout")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attribute("status", order.status.to_string())
+        .add_messages(payout_msgs)
+        .add_attribute("action", "release funds to seller")
+        .add_attributes(payout_attributes))
+}
+
+//completes a shipped order, paying the seller the price minus the configured fee plus the shipping
+fn complete_order(
+    deps: &mut DepsMut,
+    env: &Env,
+    order: &mut Order,
+) -> Result<(Vec<CosmosMsg>, Vec<Attribute>), ContractError> {
+    transition_order(order, OrderStatus::Completed, env.block.time)?;
+    release_unit(deps.storage, order, false)?;
+
+    let config = CONFIG.load(deps.storage)?;
+
+    // Pay out the seller and every fee recipient, shipping is not charged a fee
+    let shipping = Uint128::from(order.shipping_price());
+    release_escrow(deps.storage, order)?;
+    let (msgs, mut attributes) = pay_seller(deps, &config, order, order.price as u128, shipping)?;
+    attributes.push(attr("shipping to seller", shipping));
+
+    // Update transaction counts for both buyer and seller, only if their profiles exist
+    count_transaction(deps.storage, order)?;
+
+    clear_settlements(deps.storage, order)?;
+    ORDERS.save(deps.storage, order.order_id, order)?;
+    Ok((msgs, attributes))
+}
+
+//pays the seller `amount` less the configured fee plus any shipping, and every fee recipient with
+//a non-zero share
+fn pay_seller(
+    deps: &mut DepsMut,
+    config: &Config,
+    order: &Order,
+    amount: u128,
+    shipping: Uint128,
+) -> Result<(Vec<CosmosMsg>, Vec<Attribute>), ContractError> {
+    let split = split_fee(config, amount);
+    let mut msgs = payout(
+        deps,
+        config,
+        order.seller.as_str(),
+        (split.seller_amount + shipping).u128(),
+        &order.denom,
+    )?;
+    let mut attributes = vec![
+        attr("amount to seller", split.seller_amount + shipping),
+        attr("fee total", split.fee_amount),
+    ];
+    for share in split.shares.iter().filter(|share| !share.amount.is_zero()) {
+        msgs.extend(payout(
+            deps,
+            config,
+            share.address.as_str(),
+            share.amount.u128(),
+            &order.denom,
+        )?);
+        attributes.push(attr(format!("fee to {}", share.name), share.amount));
+    }
+    Ok((msgs, attributes))
+}
+
+fn count_transaction(storage: &mut dyn Storage, order: &Order) -> StdResult<()> {
+    for address in [&order.seller, &order.buyer] {
+        if let Some(mut profile) = PROFILES.may_load(storage, address.clone())? {
+            profile.transaction_count += 1;
+            PROFILES.save(storage, address.clone(), &profile)?;
+        }
+    }
+    Ok(())
+}
+
+fn milestone_index(order: &Order, milestone_id: u64) -> Result<usize, ContractError> {
+    order
+        .milestones
+        .iter()
+        .position(|milestone| milestone.milestone_id == milestone_id)
+        .ok_or(ContractError::InvalidMilestone {
+            order_id: order.order_id,
+            milestone_id,
+        })
+}
+
+//milestones go through their own state machine, recording when they were delivered and settled
+fn transition_milestone(
+    order: &mut Order,
+    index: usize,
+    to: MilestoneStatus,
+    now: Timestamp,
+) -> Result<(), ContractError> {
+    let order_id = order.order_id;
+    let milestone = &mut order.milestones[index];
+    if !milestone.status.can_transition_to(to) {
+        return Err(ContractError::InvalidMilestoneTransition {
+            order_id,
+            milestone_id: milestone.milestone_id,
+            from: milestone.status,
+            to,
+        });
+    }
+    milestone.status = to;
+    match to {
+        MilestoneStatus::Delivered => milestone.delivered_at = Some(now),
+        MilestoneStatus::Released | MilestoneStatus::Refunded => milestone.settled_at = Some(now),
+        MilestoneStatus::Pending | MilestoneStatus::Disputed => {}
+    }
+    Ok(())
+}
+
+//returns whatever is left of a cancelled service to the buyer, milestone by milestone
+fn refund_milestones(order: &mut Order, now: Timestamp) -> Result<(), ContractError> {
+    for index in 0..order.milestones.len() {
+        if !order.milestones[index].status.is_settled() {
+            transition_milestone(order, index, MilestoneStatus::Refunded, now)?;
+        }
+    }
+    Ok(())
+}
+
+//drops the settlement proposals for whatever part of an order is now settled, so none outlive it
+fn clear_settlements(storage: &mut dyn Storage, order: &Order) -> StdResult<()> {
+    let closed = matches!(order.status, OrderStatus::Completed | OrderStatus::Refunded);
+    let milestone_ids = SETTLEMENTS
+        .prefix(order.order_id)
+        .keys(storage, None, None, StorageOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    for milestone_id in milestone_ids {
+        let settled = closed
+            || order.milestones.iter().any(|milestone| {
+                milestone.milestone_id == milestone_id && milestone.status.is_settled()
+            });
+        if settled {
+            SETTLEMENTS.remove(storage, (order.order_id, milestone_id));
+        }
+    }
+    Ok(())
+}
+
+//closes a service order once every milestone is settled, completed if the seller was paid for any
+fn finish_service_order(
+    storage: &mut dyn Storage,
+    order: &mut Order,
+    now: Timestamp,
+) -> Result<(), ContractError> {
+    if !order
+        .milestones
+        .iter()
+        .all(|milestone| milestone.status.is_settled())
+    {
+        return Ok(());
+    }
+    if order
+        .milestones
+        .iter()
+        .any(|milestone| milestone.status == MilestoneStatus::Released)
+    {
+        transition_order(order, OrderStatus::Completed, now)?;
+        count_transaction(storage, order)?;
+    } else {
+        transition_order(order, OrderStatus::Refunded, now)?;
+    }
+    release_unit(storage, order, false)
+}
+
+fn execute_deliver_milestone(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+    milestone_id: u64,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    if info.sender != order.seller {
         return Err(ContractError::Unauthorized {});
     }
-    listing.arbitration_requested = true;
-    LISTING.save(deps.storage, listing_id, &listing)?;
+    let index = milestone_index(&order, milestone_id)?;
+    if order.milestones[..index]
+        .iter()
+        .any(|milestone| milestone.status == MilestoneStatus::Pending)
+    {
+        return Err(ContractError::MilestoneOutOfOrder {
+            order_id,
+            milestone_id,
+        });
+    }
+    transition_milestone(
+        &mut order,
+        index,
+        MilestoneStatus::Delivered,
+        env.block.time,
+    )?;
+    //the first delivery starts the work, from then on the buyer can't simply cancel
+    if order.status == OrderStatus::Purchased {
+        transition_order(&mut order, OrderStatus::Shipped, env.block.time)?;
+    }
+    ORDERS.save(deps.storage, order_id, &order)?;
+    Ok(Response::new()
+        .add_attribute("action", "deliver_milestone")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attribute("milestone_id", milestone_id.to_string()))
+}
+
+//the buyer signs off a delivered milestone, releasing its amount to the seller less the fee
+fn execute_approve_milestone(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+    milestone_id: u64,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    if info.sender != order.buyer {
+        return Err(ContractError::Unauthorized {});
+    }
+    let index = milestone_index(&order, milestone_id)?;
+    transition_milestone(&mut order, index, MilestoneStatus::Released, env.block.time)?;
+    let amount = order.milestones[index].amount as u128;
+    subtract_from_total(deps.storage, &ESCROWED, &order.denom, amount)?;
+    let config = CONFIG.load(deps.storage)?;
+    let (payout_msgs, payout_attributes) =
+        pay_seller(&mut deps, &config, &order, amount, Uint128::zero())?;
+    finish_service_order(deps.storage, &mut order, env.block.time)?;
+    clear_settlements(deps.storage, &order)?;
+    ORDERS.save(deps.storage, order_id, &order)?;
+    Ok(Response::new()
+        .add_messages(payout_msgs)
+        .add_attribute("action", "approve_milestone")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attribute("milestone_id", milestone_id.to_string())
+        .add_attribute("status", order.status.to_string())
+        .add_attributes(payout_attributes))
+}
+
+fn execute_request_arbitration(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+    milestone_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    // Only allow buyer or seller to request arbitration
+    if info.sender != order.seller && info.sender != order.buyer {
+        return Err(ContractError::Unauthorized {});
+    }
+    match milestone_id {
+        None if order.milestones.is_empty() => {
+            transition_order(&mut order, OrderStatus::Disputed, env.block.time)?
+        }
+        None => return Err(ContractError::ServiceOrder { order_id }),
+        //a milestone can be disputed once work on the service has started
+        Some(milestone_id) => {
+            let index = milestone_index(&order, milestone_id)?;
+            if order.status != OrderStatus::Shipped {
+                return Err(ContractError::InvalidOrderTransition {
+                    order_id,
+                    from: order.status,
+                    to: OrderStatus::Disputed,
+                });
+            }
+            transition_milestone(&mut order, index, MilestoneStatus::Disputed, env.block.time)?;
+        }
+    }
+    ORDERS.save(deps.storage, order_id, &order)?;
     Ok(Response::new()
         .add_attribute("action", "request_arbitration")
-        .add_attribute("post_id", listing_id.to_string()))
+        .add_attribute("order_id", order_id.to_string())
+        .add_attributes(
+            milestone_id.map(|milestone_id| attr("milestone_id", milestone_id.to_string())),
+        )
+        .add_attribute("status", order.status.to_string()))
+}
+
+//Either side of an order in escrow can offer the other a split, without waiting for an arbiter
+fn execute_propose_settlement(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+    milestone_id: Option<u64>,
+    buyer_share_bps: u64,
+) -> Result<Response, ContractError> {
+    let order = ORDERS.load(deps.storage, order_id)?;
+    if info.sender != order.seller && info.sender != order.buyer {
+        return Err(ContractError::Unauthorized {});
+    }
+    if buyer_share_bps as u128 > BPS_DENOMINATOR {
+        return Err(ContractError::InvalidSettlementShare {});
+    }
+    match milestone_id {
+        //only a delivered or disputed milestone can be split, like a shipped order
+        Some(milestone_id) => {
+            let milestone = &order.milestones[milestone_index(&order, milestone_id)?];
+            if !matches!(
+                milestone.status,
+                MilestoneStatus::Delivered | MilestoneStatus::Disputed
+            ) {
+                return Err(ContractError::InvalidMilestoneTransition {
+                    order_id,
+                    milestone_id,
+                    from: milestone.status,
+                    to: MilestoneStatus::Released,
+                });
+            }
+        }
+        None if !order.milestones.is_empty() => {
+            return Err(ContractError::ServiceOrder { order_id })
+        }
+        None => {}
+    }
+    //before shipping the buyer can simply cancel
+    if !matches!(order.status, OrderStatus::Shipped | OrderStatus::Disputed) {
+        return Err(ContractError::InvalidOrderTransition {
+            order_id,
+            from: order.status,
+            to: OrderStatus::Completed,
+        });
+    }
+    let settlement = Settlement {
+        order_id,
+        milestone_id,
+        proposer: info.sender.clone(),
+        buyer_share_bps,
+        proposed_at: env.block.time,
+    };
+    SETTLEMENTS.save(
+        deps.storage,
+        (order_id, milestone_id.unwrap_or(0)),
+        &settlement,
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_settlement")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attributes(
+            milestone_id.map(|milestone_id| attr("milestone_id", milestone_id.to_string())),
+        )
+        .add_attribute("proposer", info.sender.to_string())
+        .add_attribute("buyer_share_bps", buyer_share_bps.to_string()))
+}
+
+//Splits the escrow as proposed, the seller's share is charged the marketplace fee like any sale
+fn execute_accept_settlement(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+    milestone_id: Option<u64>,
+    buyer_share_bps: u64,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    let key = (order_id, milestone_id.unwrap_or(0));
+    let settlement = SETTLEMENTS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NoSettlement { order_id })?;
+    //only the side that didn't propose can accept
+    if (info.sender != order.seller && info.sender != order.buyer)
+        || info.sender == settlement.proposer
+    {
+        return Err(ContractError::Unauthorized {});
+    }
+    //guards against the proposal being swapped just before it is accepted
+    if settlement.buyer_share_bps != buyer_share_bps {
+        return Err(ContractError::SettlementChanged {
+            order_id,
+            buyer_share_bps: settlement.buyer_share_bps,
+        });
+    }
+    let disputed = match milestone_id {
+        Some(milestone_id) => {
+            order.milestones[milestone_index(&order, milestone_id)?].status
+                == MilestoneStatus::Disputed
+        }
+        None => order.status == OrderStatus::Disputed,
+    };
+    let status = if buyer_share_bps as u128 == BPS_DENOMINATOR {
+        OrderStatus::Refunded
+    } else {
+        OrderStatus::Completed
+    };
+    let (amount, attributes) = close_split(deps.storage, &env, &mut order, milestone_id, status)?;
+    ORDERS.save(deps.storage, order_id, &order)?;
+
+    let amounts = split_by_bps(
+        amount as u128,
+        &[buyer_share_bps, BPS_DENOMINATOR as u64 - buyer_share_bps],
+    );
+    let config = CONFIG.load(deps.storage)?;
+    let mut resp = Response::new()
+        .add_attribute("action", "accept_settlement")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attributes(attributes)
+        .add_attribute("arbitration_cancelled", disputed.to_string());
+    if amounts[0] > 0 {
+        let msgs = payout(
+            &mut deps,
+            &config,
+            order.buyer.as_str(),
+            amounts[0],
+            &order.denom,
+        )?;
+        resp = resp
+            .add_messages(msgs)
+            .add_attribute("to buyer", amounts[0].to_string());
+    }
+    //the fee is charged on the seller's share of the price, not of the shipping
+    if amounts[1] > 0 {
+        let shipping = shipping_share(&order, milestone_id, amounts[1]);
+        let (msgs, attributes) = pay_seller(
+            &mut deps,
+            &config,
+            &order,
+            amounts[1] - shipping.u128(),
+            shipping,
+        )?;
+        resp = resp.add_messages(msgs).add_attributes(attributes);
+    }
+    Ok(resp.add_attribute("status", order.status.to_string()))
+}
+
+fn execute_revoke_settlement(
+    deps: DepsMut,
+    info: MessageInfo,
+    order_id: u64,
+    milestone_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let key = (order_id, milestone_id.unwrap_or(0));
+    let settlement = SETTLEMENTS
+        .may_load(deps.storage, key)?
+        .ok_or(ContractError::NoSettlement { order_id })?;
+    if info.sender != settlement.proposer {
+        return Err(ContractError::Unauthorized {});
+    }
+    SETTLEMENTS.remove(deps.storage, key);
+    Ok(Response::new()
+        .add_attribute("action", "revoke_settlement")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attributes(
+            milestone_id.map(|milestone_id| attr("milestone_id", milestone_id.to_string())),
+        ))
+}
+
+//loads a listing that is still for sale in a denom the contract accepts
+fn load_purchasable_listing(deps: Deps, listing_id: u64) -> Result<Listing, ContractError> {
+    let listing = LISTING.load(deps.storage, listing_id)?;
+    if listing.sale_type.takes_bids() {
+        return Err(ContractError::WrongSaleType {
+            listing_id,
+            sale_type: listing.sale_type.to_string(),
+        });
+    }
+    if listing.quantity == 0 {
+        return Err(ContractError::SoldOut { listing_id });
+    }
+    //the admin may have stopped accepting the denom since the listing was created
+    let config = CONFIG.load(deps.storage)?;
+    if !config.accepted_denoms.contains(&listing.denom) {
+        return Err(ContractError::InvalidDenom {
+            denom: listing.denom,
+        });
+    }
+    Ok(listing)
+}
+
+//reserves a unit of a paid-for listing, opens an order for the buyer and returns anything paid beyond the price
+fn save_purchase(
+    deps: DepsMut,
+    env: &Env,
+    listing: Listing,
+    variant_id: Option<u64>,
+    shipping: Option<ShippingOption>,
+    buyer: &Addr,
+    payment: PaymentBreakdown,
+) -> Result<Response, ContractError> {
+    let listing_id = listing.listing_id;
+    let price = unit_price(&listing, variant_id, env.block.time)?;
+    let order = open_order(
+        deps.storage,
+        deps.api,
+        env,
+        listing,
+        variant_id,
+        shipping,
+        buyer,
+        price,
+    )?;
+    escrow_order(deps.storage, &order)?;
+    Ok(Response::new()
+        .add_messages(payment.refund_msgs(buyer.as_str())?)
+        .add_attribute("action", "purchase")
+        .add_attribute("post_id", listing_id.to_string())
+        .add_attribute("order_id", order.order_id.to_string())
+        .add_attribute("buyer", buyer.to_string())
+        .add_attributes(payment.attributes()))
+}
+
+//takes a unit out of stock and records the order for it, the caller escrows the payment
+#[allow(clippy::too_many_arguments)]
+fn open_order(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    env: &Env,
+    mut listing: Listing,
+    variant_id: Option<u64>,
+    shipping: Option<ShippingOption>,
+    buyer: &Addr,
+    price: u64,
+) -> Result<Order, ContractError> {
+    reserve_unit(&mut listing, variant_id)?;
+    LISTING.save(storage, listing.listing_id, &listing)?;
+    let ship_window = match listing.ship_within {
+        Some(ship_within) => ship_within,
+        None => CONFIG.load(storage)?.ship_window,
+    };
+    let milestones = match &listing.sale_type {
+        SaleType::Service(service) => service
+            .milestones
+            .iter()
+            .zip(1..)
+            .map(|(terms, milestone_id)| Milestone {
+                milestone_id,
+                description: terms.description.clone(),
+                amount: terms.amount,
+                status: MilestoneStatus::Pending,
+                delivered_at: None,
+                settled_at: None,
+            })
+            .collect(),
+        _ => vec![],
+    };
+    let order = Order {
+        order_id: LAST_ORDER_ID.load(storage)? + 1,
+        listing_id: Some(listing.listing_id),
+        wanted_ad_id: None,
+        buyer: buyer.clone(),
+        seller: api.addr_validate(&listing.seller)?,
+        variant_id,
+        price,
+        denom: listing.denom,
+        shipping,
+        status: OrderStatus::Purchased,
+        purchased_at: env.block.time,
+        ship_by: env.block.time.plus_seconds(ship_window),
+        shipped_at: None,
+        disputed_at: None,
+        completed_at: None,
+        refunded_at: None,
+        confirm_by: None,
+        shipment_id: None,
+        milestones,
+    };
+    save_new_order(storage, &order)?;
+    Ok(order)
+}
+
+//stores an order under the next order id and indexes it for both sides
+fn save_new_order(storage: &mut dyn Storage, order: &Order) -> StdResult<()> {
+    LAST_ORDER_ID.save(storage, &order.order_id)?;
+    ORDERS.save(storage, order.order_id, order)?;
+    ORDERS_BY_ADDRESS.save(storage, (order.buyer.clone(), order.order_id), &())?;
+    ORDERS_BY_ADDRESS.save(storage, (order.seller.clone(), order.order_id), &())?;
+    Ok(())
+}
+
+//where an order came from, for event attributes
+fn order_source(order: &Order) -> Attribute {
+    match order.listing_id {
+        Some(listing_id) => attr("listing_id", listing_id.to_string()),
+        None => attr(
+            "wanted_ad_id",
+            order.wanted_ad_id.unwrap_or_default().to_string(),
+        ),
+    }
 }
 
 fn execute_purchase(
     deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+    variant_id: Option<u64>,
+    shipping_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let listing = load_purchasable_listing(deps.as_ref(), listing_id)?;
+    //cw20 priced listings are bought by sending tokens through Receive
+    if cw20_contract(&listing.denom).is_some() {
+        return Err(ContractError::InvalidDenom {
+            denom: listing.denom,
+        });
+    }
+    let price = unit_price(&listing, variant_id, env.block.time)?;
+    let shipping = shipping_option(&listing, shipping_id)?;
+    let total = with_shipping(price, shipping.as_ref());
+    let payment = check_payment(&info.funds, &listing.denom, total as u128)?;
+    save_purchase(
+        deps,
+        &env,
+        listing,
+        variant_id,
+        shipping,
+        &info.sender,
+        payment,
+    )
+}
+
+fn execute_purchase_many(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_ids: Vec<u64>,
+    group_by_seller: bool,
+    region: Option<String>,
+) -> Result<Response, ContractError> {
+    let (cart, total) = load_cart(deps.as_ref(), &env, &listing_ids, region.as_deref())?;
+    let denom = cart[0].listing.denom.clone();
+    if cw20_contract(&denom).is_some() {
+        return Err(ContractError::InvalidDenom { denom });
+    }
+    let payment = check_payment(&info.funds, &denom, total)?;
+    save_cart(deps, &env, cart, &info.sender, group_by_seller, payment)
+}
+
+struct CartItem {
+    listing: Listing,
+    price: u64,
+    shipping: Option<ShippingOption>,
+}
+
+//every listing in a cart with what it sells and ships for, and their total. A cart is paid in one denom
+fn load_cart(
+    deps: Deps,
+    env: &Env,
+    listing_ids: &[u64],
+    region: Option<&str>,
+) -> Result<(Vec<CartItem>, u128), ContractError> {
+    let mut cart: Vec<CartItem> = vec![];
+    for listing_id in listing_ids {
+        let listing = load_purchasable_listing(deps, *listing_id)?;
+        if let Some(first) = cart.first() {
+            if listing.denom != first.listing.denom {
+                return Err(ContractError::MixedCartDenoms {
+                    expected: first.listing.denom.clone(),
+                    denom: listing.denom,
+                });
+            }
+        }
+        let price = unit_price(&listing, None, env.block.time)?;
+        let shipping = shipping_to_region(&listing, region)?;
+        cart.push(CartItem {
+            listing,
+            price,
+            shipping,
+        });
+    }
+    if cart.is_empty() {
+        return Err(ContractError::EmptyCart {});
+    }
+    let total = cart
+        .iter()
+        .map(|item| with_shipping(item.price, item.shipping.as_ref()) as u128)
+        .sum();
+    Ok((cart, total))
+}
+
+//opens an order per cart item against the one payment, any failure reverts the whole cart
+fn save_cart(
+    deps: DepsMut,
+    env: &Env,
+    cart: Vec<CartItem>,
+    buyer: &Addr,
+    group_by_seller: bool,
+    payment: PaymentBreakdown,
+) -> Result<Response, ContractError> {
+    //only sellers with more than one item to ship in the cart get a shared shipment, services
+    //aren't shipped
+    let ships = |item: &CartItem| !matches!(item.listing.sale_type, SaleType::Service(_));
+    let grouped: Vec<bool> = cart
+        .iter()
+        .map(|item| {
+            group_by_seller
+                && ships(item)
+                && cart
+                    .iter()
+                    .filter(|other| ships(other) && other.listing.seller == item.listing.seller)
+                    .count()
+                    > 1
+        })
+        .collect();
+    let mut shipments: BTreeMap<String, u64> = BTreeMap::new();
+    let mut order_ids = vec![];
+    for (item, grouped) in cart.into_iter().zip(grouped) {
+        //reloaded, an earlier item in the cart may have taken the last unit
+        let listing = load_purchasable_listing(deps.as_ref(), item.listing.listing_id)?;
+        let seller = listing.seller.clone();
+        let mut order = open_order(
+            deps.storage,
+            deps.api,
+            env,
+            listing,
+            None,
+            item.shipping,
+            buyer,
+            item.price,
+        )?;
+        escrow_order(deps.storage, &order)?;
+        if grouped {
+            let shipment_id = *shipments.entry(seller).or_insert(order.order_id);
+            order.shipment_id = Some(shipment_id);
+            ORDERS.save(deps.storage, order.order_id, &order)?;
+            SHIPMENTS.save(deps.storage, (shipment_id, order.order_id), &())?;
+        }
+        order_ids.push(order.order_id.to_string());
+    }
+    Ok(Response::new()
+        .add_messages(payment.refund_msgs(buyer.as_str())?)
+        .add_attribute("action", "purchase_many")
+        .add_attribute("order_ids", order_ids.join(","))
+        .add_attribute("buyer", buyer.to_string())
+        .add_attributes(payment.attributes()))
+}
+
+//entry point for cw20 payments, info.sender is the token contract and msg.sender the buyer
+fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let denom = cw20_denom(&info.sender);
+    let buyer = deps.api.addr_validate(&msg.sender)?;
+    match from_json(&msg.msg)? {
+        ReceiveMsg::Purchase {
+            listing_id,
+            variant_id,
+            shipping_id,
+        } => {
+            let listing = load_purchasable_listing(deps.as_ref(), listing_id)?;
+            if listing.denom != denom {
+                return Err(ContractError::InvalidDenom { denom });
+            }
+            let price = unit_price(&listing, variant_id, env.block.time)?;
+            let shipping = shipping_option(&listing, shipping_id)?;
+            let total = with_shipping(price, shipping.as_ref());
+            let payment = check_payment(
+                &[coin(msg.amount.u128(), &denom)],
+                &listing.denom,
+                total as u128,
+            )?;
+            save_purchase(deps, &env, listing, variant_id, shipping, &buyer, payment)
+        }
+        ReceiveMsg::PurchaseMany {
+            listing_ids,
+            group_by_seller,
+            region,
+        } => {
+            let (cart, total) = load_cart(deps.as_ref(), &env, &listing_ids, region.as_deref())?;
+            if cart[0].listing.denom != denom {
+                return Err(ContractError::InvalidDenom { denom });
+            }
+            let payment = check_payment(&[coin(msg.amount.u128(), &denom)], &denom, total)?;
+            save_cart(deps, &env, cart, &buyer, group_by_seller, payment)
+        }
+        ReceiveMsg::PlaceBid { listing_id } => {
+            let (listing, minimum) = load_biddable_listing(deps.as_ref(), &env, listing_id)?;
+            if listing.denom != denom {
+                return Err(ContractError::InvalidDenom { denom });
+            }
+            let payment = check_bid(&[coin(msg.amount.u128(), &denom)], &denom, minimum)?;
+            save_bid(deps, &env, listing, &buyer, payment)
+        }
+        ReceiveMsg::CommitBid {
+            listing_id,
+            commitment,
+        } => {
+            let (listing, min_deposit) = load_committable_listing(deps.as_ref(), &env, listing_id)?;
+            if listing.denom != denom {
+                return Err(ContractError::InvalidDenom { denom });
+            }
+            let payment = check_bid(&[coin(msg.amount.u128(), &denom)], &denom, min_deposit)?;
+            save_commitment(deps, &env, listing, &buyer, commitment, payment)
+        }
+        ReceiveMsg::MakeOffer {
+            listing_id,
+            variant_id,
+            shipping_id,
+            price,
+            expires_at,
+        } => {
+            let listing = load_purchasable_listing(deps.as_ref(), listing_id)?;
+            if listing.denom != denom {
+                return Err(ContractError::InvalidDenom { denom });
+            }
+            let shipping = shipping_option(&listing, shipping_id)?;
+            let total = with_shipping(price, shipping.as_ref());
+            let payment = check_payment(&[coin(msg.amount.u128(), &denom)], &denom, total as u128)?;
+            save_offer(
+                deps, &env, listing, variant_id, shipping, &buyer, price, expires_at, payment,
+            )
+        }
+        ReceiveMsg::PostWantedAd {
+            title,
+            text,
+            tags,
+            budget,
+        } => {
+            let payment =
+                check_payment(&[coin(msg.amount.u128(), &denom)], &denom, budget as u128)?;
+            save_wanted_ad(deps, &env, &buyer, title, text, tags, budget, payment)
+        }
+        ReceiveMsg::AcceptOffer { offer_id } => {
+            let offer = load_open_offer(deps.storage, offer_id)?;
+            if offer.denom != denom {
+                return Err(ContractError::InvalidDenom { denom });
+            }
+            accept_offer(
+                deps,
+                &env,
+                &buyer,
+                offer,
+                &[coin(msg.amount.u128(), &denom)],
+            )
+        }
+    }
+}
+
+//validates an offer on a listing that can be bought outright and escrows it
+#[allow(clippy::too_many_arguments)]
+fn save_offer(
+    deps: DepsMut,
+    env: &Env,
+    listing: Listing,
+    variant_id: Option<u64>,
+    shipping: Option<ShippingOption>,
+    buyer: &Addr,
+    price: u64,
+    expires_at: Timestamp,
+    payment: PaymentBreakdown,
+) -> Result<Response, ContractError> {
+    if buyer.as_str() == listing.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    //a service's price is split across its milestones
+    if let SaleType::Service(_) = listing.sale_type {
+        return Err(ContractError::WrongSaleType {
+            listing_id: listing.listing_id,
+            sale_type: listing.sale_type.to_string(),
+        });
+    }
+    //offering the list price or more is just a purchase
+    let list_price = unit_price(&listing, variant_id, env.block.time)?;
+    if price == 0 || price >= list_price {
+        return Err(ContractError::InvalidOfferPrice {
+            price,
+            min: 1,
+            max: list_price.saturating_sub(1),
+        });
+    }
+    if expires_at <= env.block.time {
+        return Err(ContractError::InvalidExpiry {});
+    }
+    let offer_id = LAST_OFFER_ID.load(deps.storage)? + 1;
+    let offer = Offer {
+        offer_id,
+        listing_id: listing.listing_id,
+        buyer: buyer.clone(),
+        variant_id,
+        price,
+        counter_price: None,
+        denom: listing.denom,
+        shipping,
+        status: OfferStatus::Open,
+        created_at: env.block.time,
+        expires_at,
+        order_id: None,
+    };
+    LAST_OFFER_ID.save(deps.storage, &offer_id)?;
+    OFFERS.save(deps.storage, offer_id, &offer)?;
+    OPEN_OFFERS.save(deps.storage, (offer.listing_id, offer_id), &())?;
+    add_to_total(
+        deps.storage,
+        &ESCROWED,
+        &offer.denom,
+        offer.escrowed() as u128,
+    )?;
+    Ok(Response::new()
+        .add_messages(payment.refund_msgs(buyer.as_str())?)
+        .add_attribute("action", "make_offer")
+        .add_attribute("listing_id", offer.listing_id.to_string())
+        .add_attribute("offer_id", offer_id.to_string())
+        .add_attribute("buyer", buyer.to_string())
+        .add_attribute("price", price.to_string())
+        .add_attributes(payment.attributes()))
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_make_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+    variant_id: Option<u64>,
+    shipping_id: Option<u64>,
+    price: u64,
+    expires_at: Timestamp,
+) -> Result<Response, ContractError> {
+    let listing = load_purchasable_listing(deps.as_ref(), listing_id)?;
+    //cw20 offers are escrowed by sending tokens through Receive
+    if cw20_contract(&listing.denom).is_some() {
+        return Err(ContractError::InvalidDenom {
+            denom: listing.denom,
+        });
+    }
+    let shipping = shipping_option(&listing, shipping_id)?;
+    let total = with_shipping(price, shipping.as_ref());
+    let payment = check_payment(&info.funds, &listing.denom, total as u128)?;
+    save_offer(
+        deps,
+        &env,
+        listing,
+        variant_id,
+        shipping,
+        &info.sender,
+        price,
+        expires_at,
+        payment,
+    )
+}
+
+//loads an offer still waiting on the seller or the buyer
+fn load_open_offer(storage: &dyn Storage, offer_id: u64) -> Result<Offer, ContractError> {
+    let offer = OFFERS.load(storage, offer_id)?;
+    if !offer.status.is_open() {
+        return Err(ContractError::OfferClosed {
+            offer_id,
+            status: offer.status,
+        });
+    }
+    Ok(offer)
+}
+
+//closes an open offer and returns the escrowed funds to the buyer
+fn close_offer(
+    deps: &mut DepsMut,
+    offer: &mut Offer,
+    status: OfferStatus,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    offer.status = status;
+    OFFERS.save(deps.storage, offer.offer_id, offer)?;
+    OPEN_OFFERS.remove(deps.storage, (offer.listing_id, offer.offer_id));
+    subtract_from_total(
+        deps.storage,
+        &ESCROWED,
+        &offer.denom,
+        offer.escrowed() as u128,
+    )?;
+    let config = CONFIG.load(deps.storage)?;
+    payout(
+        deps,
+        &config,
+        offer.buyer.as_str(),
+        offer.escrowed() as u128,
+        &offer.denom,
+    )
+}
+
+//refunds every offer still open on a listing that can no longer be bought, expired ones as Expired
+fn reject_open_offers(
+    deps: &mut DepsMut,
+    env: &Env,
+    listing_id: u64,
+) -> Result<Vec<CosmosMsg>, ContractError> {
+    let offer_ids = OPEN_OFFERS
+        .prefix(listing_id)
+        .keys(deps.storage, None, None, StorageOrder::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let mut msgs = vec![];
+    for offer_id in offer_ids {
+        let mut offer = OFFERS.load(deps.storage, offer_id)?;
+        let status = if env.block.time >= offer.expires_at {
+            OfferStatus::Expired
+        } else {
+            OfferStatus::Rejected
+        };
+        msgs.extend(close_offer(deps, &mut offer, status)?);
+    }
+    Ok(msgs)
+}
+
+//turns an offer into an order at the agreed price, the buyer's top-up for a counter is in `funds`
+fn accept_offer(
+    mut deps: DepsMut,
+    env: &Env,
+    sender: &Addr,
+    mut offer: Offer,
+    funds: &[Coin],
+) -> Result<Response, ContractError> {
+    let listing = load_purchasable_listing(deps.as_ref(), offer.listing_id)?;
+    //the seller can always take the buyer's own price, the buyer can only take a counter
+    let agreed_price = if sender.as_str() == listing.seller {
+        if funds.iter().any(|sent| !sent.amount.is_zero()) {
+            return Err(ContractError::UnexpectedFunds {});
+        }
+        offer.price
+    } else if let (true, Some(counter_price)) = (sender == offer.buyer, offer.counter_price) {
+        counter_price
+    } else {
+        return Err(ContractError::Unauthorized {});
+    };
+    if env.block.time >= offer.expires_at {
+        return Err(ContractError::OfferExpired {
+            offer_id: offer.offer_id,
+        });
+    }
+    //checks the variant is still in stock, and never sells above the price a Dutch auction has decayed to
+    let price = agreed_price.min(unit_price(&listing, offer.variant_id, env.block.time)?);
+    //the buyer only tops up to the price actually charged
+    let payment = if sender == offer.buyer {
+        let top_up = price.saturating_sub(offer.price);
+        Some(check_payment(funds, &offer.denom, top_up as u128)?)
+    } else {
+        None
+    };
+    let order = open_order(
+        deps.storage,
+        deps.api,
+        env,
+        listing,
+        offer.variant_id,
+        offer.shipping.clone(),
+        &offer.buyer,
+        price,
+    )?;
+    //the order takes over the offer's escrow, plus any top-up or less whatever the price dropped by
+    let mut msgs = match payment {
+        Some(payment) => payment.refund_msgs(offer.buyer.as_str())?,
+        None => vec![],
+    };
+    if price >= offer.price {
+        add_to_total(
+            deps.storage,
+            &ESCROWED,
+            &offer.denom,
+            (price - offer.price) as u128,
+        )?;
+    } else {
+        let refund = (offer.price - price) as u128;
+        subtract_from_total(deps.storage, &ESCROWED, &offer.denom, refund)?;
+        let config = CONFIG.load(deps.storage)?;
+        msgs.extend(payout(
+            &mut deps,
+            &config,
+            offer.buyer.as_str(),
+            refund,
+            &offer.denom,
+        )?);
+    }
+    offer.status = OfferStatus::Accepted;
+    offer.order_id = Some(order.order_id);
+    OFFERS.save(deps.storage, offer.offer_id, &offer)?;
+    OPEN_OFFERS.remove(deps.storage, (offer.listing_id, offer.offer_id));
+    //competing offers are refunded once the accepted one takes the last unit
+    let listing = LISTING.load(deps.storage, offer.listing_id)?;
+    if listing.status == ListingStatus::SoldOut {
+        msgs.extend(reject_open_offers(&mut deps, env, offer.listing_id)?);
+    }
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "accept_offer")
+        .add_attribute("offer_id", offer.offer_id.to_string())
+        .add_attribute("order_id", order.order_id.to_string())
+        .add_attribute("price", price.to_string()))
+}
+
+fn execute_accept_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_id: u64,
+) -> Result<Response, ContractError> {
+    let offer = load_open_offer(deps.storage, offer_id)?;
+    accept_offer(deps, &env, &info.sender, offer, &info.funds)
+}
+
+fn execute_reject_offer(
+    mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
+    offer_id: u64,
+) -> Result<Response, ContractError> {
+    let mut offer = load_open_offer(deps.storage, offer_id)?;
+    let listing = LISTING.load(deps.storage, offer.listing_id)?;
+    if info.sender.as_str() != listing.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    let refund_msgs = close_offer(&mut deps, &mut offer, OfferStatus::Rejected)?;
+    Ok(Response::new()
+        .add_messages(refund_msgs)
+        .add_attribute("action", "reject_offer")
+        .add_attribute("offer_id", offer_id.to_string()))
+}
+
+//the seller names a price between the offer and the list price for the buyer to accept
+fn execute_counter_offer(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_id: u64,
+    price: u64,
+) -> Result<Response, ContractError> {
+    let mut offer = load_open_offer(deps.storage, offer_id)?;
+    let listing = LISTING.load(deps.storage, offer.listing_id)?;
+    if info.sender.as_str() != listing.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    if env.block.time >= offer.expires_at {
+        return Err(ContractError::OfferExpired { offer_id });
+    }
+    let list_price = unit_price(&listing, offer.variant_id, env.block.time)?;
+    if price <= offer.price || price > list_price {
+        return Err(ContractError::InvalidOfferPrice {
+            price,
+            min: offer.price + 1,
+            max: list_price,
+        });
+    }
+    offer.counter_price = Some(price);
+    offer.status = OfferStatus::Countered;
+    OFFERS.save(deps.storage, offer_id, &offer)?;
+    Ok(Response::new()
+        .add_attribute("action", "counter_offer")
+        .add_attribute("offer_id", offer_id.to_string())
+        .add_attribute("price", price.to_string()))
+}
+
+fn execute_withdraw_offer(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    offer_id: u64,
+) -> Result<Response, ContractError> {
+    let mut offer = load_open_offer(deps.storage, offer_id)?;
+    let status = if info.sender == offer.buyer {
+        OfferStatus::Withdrawn
+    } else if env.block.time >= offer.expires_at {
+        OfferStatus::Expired
+    } else {
+        return Err(ContractError::Unauthorized {});
+    };
+    let refund_msgs = close_offer(&mut deps, &mut offer, status)?;
+    Ok(Response::new()
+        .add_messages(refund_msgs)
+        .add_attribute("action", "withdraw_offer")
+        .add_attribute("offer_id", offer_id.to_string())
+        .add_attribute("status", offer.status.to_string()))
+}
+
+//loads an auction still taking bids, along with the smallest bid it will accept
+fn load_biddable_listing(
+    deps: Deps,
+    env: &Env,
+    listing_id: u64,
+) -> Result<(Listing, u128), ContractError> {
+    let listing = LISTING.load(deps.storage, listing_id)?;
+    let SaleType::EnglishAuction(auction) = &listing.sale_type else {
+        return Err(ContractError::WrongSaleType {
+            listing_id,
+            sale_type: listing.sale_type.to_string(),
+        });
+    };
+    if listing.status != ListingStatus::Active || env.block.time >= auction.end_time {
+        return Err(ContractError::AuctionEnded { listing_id });
+    }
+    let minimum = match &auction.highest_bid {
+        Some(bid) => bid.amount as u128 + auction.min_increment as u128,
+        None => auction.start_price as u128,
+    };
+    let config = CONFIG.load(deps.storage)?;
+    if !config.accepted_denoms.contains(&listing.denom) {
+        return Err(ContractError::InvalidDenom {
+            denom: listing.denom,
+        });
+    }
+    Ok((listing, minimum))
+}
+
+//escrows the new highest bid and refunds the one it beat
+fn save_bid(
+    mut deps: DepsMut,
+    env: &Env,
+    mut listing: Listing,
+    bidder: &Addr,
+    payment: PaymentBreakdown,
+) -> Result<Response, ContractError> {
+    if bidder.as_str() == listing.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    let amount = Uint64::try_from(Uint128::new(payment.received))
+        .map_err(StdError::from)?
+        .u64();
+    let SaleType::EnglishAuction(auction) = &mut listing.sale_type else {
+        unreachable!("load_biddable_listing only returns auctions");
+    };
+    auction.bid_count += 1;
+    let bid = Bid {
+        bid_id: auction.bid_count,
+        bidder: bidder.clone(),
+        amount,
+        placed_at: env.block.time,
+    };
+    //anti-sniping, a late bid gives everyone else extension_seconds to answer it
+    let extended_end = env.block.time.plus_seconds(auction.extension_seconds);
+    if extended_end > auction.end_time {
+        auction.end_time = extended_end;
+    }
+    let outbid = auction.highest_bid.replace(bid.clone());
+    let end_time = auction.end_time;
+    BIDS.save(deps.storage, (listing.listing_id, bid.bid_id), &bid)?;
+    LISTING.save(deps.storage, listing.listing_id, &listing)?;
+    add_to_total(deps.storage, &ESCROWED, &listing.denom, amount as u128)?;
+    let mut resp = Response::new()
+        .add_messages(payment.refund_msgs(bidder.as_str())?)
+        .add_attribute("action", "place_bid")
+        .add_attribute("listing_id", listing.listing_id.to_string())
+        .add_attribute("bid_id", bid.bid_id.to_string())
+        .add_attribute("bidder", bidder.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("end_time", end_time.to_string());
+    if let Some(outbid) = outbid {
+        let config = CONFIG.load(deps.storage)?;
+        subtract_from_total(
+            deps.storage,
+            &ESCROWED,
+            &listing.denom,
+            outbid.amount as u128,
+        )?;
+        let refund_msgs = payout(
+            &mut deps,
+            &config,
+            outbid.bidder.as_str(),
+            outbid.amount as u128,
+            &listing.denom,
+        )?;
+        resp = resp
+            .add_messages(refund_msgs)
+            .add_attribute("outbid", outbid.bidder.to_string());
+    }
+    Ok(resp)
+}
+
+fn execute_place_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+) -> Result<Response, ContractError> {
+    let (listing, minimum) = load_biddable_listing(deps.as_ref(), &env, listing_id)?;
+    //cw20 auctions are bid on by sending tokens through Receive
+    if cw20_contract(&listing.denom).is_some() {
+        return Err(ContractError::InvalidDenom {
+            denom: listing.denom,
+        });
+    }
+    let payment = check_bid(&info.funds, &listing.denom, minimum)?;
+    save_bid(deps, &env, listing, &info.sender, payment)
+}
+
+//closes an ended auction, a winning bid at or above the reserve becomes an order in the usual
+//escrow flow and anything short of it goes back to the bidder
+fn execute_settle_auction(
+    mut deps: DepsMut,
+    env: Env,
     listing_id: u64,
 ) -> Result<Response, ContractError> {
     let mut listing = LISTING.load(deps.storage, listing_id)?;
-    if listing.bought {
-        return Err(ContractError::AlreadyPurchased {});
+    //reveals below a sealed bid auction's reserve never become the highest bid
+    let (end_time, reserve_price, highest_bid) = match &listing.sale_type {
+        SaleType::EnglishAuction(auction) => (
+            auction.end_time,
+            auction.reserve_price,
+            auction.highest_bid.clone(),
+        ),
+        SaleType::SealedBidAuction(auction) => (auction.reveal_end, 0, auction.highest_bid.clone()),
+        _ => {
+            return Err(ContractError::WrongSaleType {
+                listing_id,
+                sale_type: listing.sale_type.to_string(),
+            })
+        }
+    };
+    //already settled
+    if listing.status != ListingStatus::Active {
+        return Err(ContractError::InvalidStatusTransition {
+            listing_id,
+            from: listing.status,
+            to: ListingStatus::Ended,
+        });
+    }
+    if env.block.time < end_time {
+        return Err(ContractError::AuctionNotEnded {
+            listing_id,
+            end_time,
+        });
+    }
+    let config = CONFIG.load(deps.storage)?;
+    let resp = Response::new()
+        .add_attribute("action", "settle_auction")
+        .add_attribute("listing_id", listing_id.to_string());
+    match highest_bid {
+        //the bid is already escrowed, the order just takes it over
+        Some(bid) if bid.amount >= reserve_price => {
+            let mut refund_msgs = vec![];
+            //the winning deposit pays for the order and whatever it held beyond the bid goes back
+            if let SaleType::SealedBidAuction(auction) = &mut listing.sale_type {
+                auction.deposits_held -= 1;
+                let key = (listing_id, bid.bidder.clone());
+                let sealed = SEALED_BIDS.load(deps.storage, key.clone())?;
+                SEALED_BIDS.remove(deps.storage, key);
+                let excess = (sealed.deposit - bid.amount) as u128;
+                if excess > 0 {
+                    subtract_from_total(deps.storage, &ESCROWED, &listing.denom, excess)?;
+                    refund_msgs = payout(
+                        &mut deps,
+                        &config,
+                        bid.bidder.as_str(),
+                        excess,
+                        &listing.denom,
+                    )?;
+                }
+            }
+            let order = open_order(
+                deps.storage,
+                deps.api,
+                &env,
+                listing,
+                None,
+                None,
+                &bid.bidder,
+                bid.amount,
+            )?;
+            Ok(resp
+                .add_messages(refund_msgs)
+                .add_attribute("winner", bid.bidder.to_string())
+                .add_attribute("amount", bid.amount.to_string())
+                .add_attribute("order_id", order.order_id.to_string()))
+        }
+        highest_bid => {
+            transition(&mut listing, ListingStatus::Ended)?;
+            LISTING.save(deps.storage, listing_id, &listing)?;
+            let mut resp = resp.add_attribute("winner", "none");
+            if let Some(bid) = highest_bid {
+                subtract_from_total(deps.storage, &ESCROWED, &listing.denom, bid.amount as u128)?;
+                let refund_msgs = payout(
+                    &mut deps,
+                    &config,
+                    bid.bidder.as_str(),
+                    bid.amount as u128,
+                    &listing.denom,
+                )?;
+                resp = resp.add_messages(refund_msgs);
+            }
+            Ok(resp)
+        }
+    }
+}
+
+//what a sealed bidder commits to, binding the bid to the listing and bidder so it can't be replayed
+pub fn sealed_bid_commitment(listing_id: u64, bidder: &Addr, amount: u64, salt: &str) -> Binary {
+    let preimage = format!("{}:{}:{}:{}", listing_id, bidder, amount, salt);
+    Binary::from(Sha256::digest(preimage.as_bytes()).to_vec())
+}
+
+//loads a sealed bid auction still taking commitments, along with its minimum deposit
+fn load_committable_listing(
+    deps: Deps,
+    env: &Env,
+    listing_id: u64,
+) -> Result<(Listing, u128), ContractError> {
+    let listing = LISTING.load(deps.storage, listing_id)?;
+    let SaleType::SealedBidAuction(auction) = &listing.sale_type else {
+        return Err(ContractError::WrongSaleType {
+            listing_id,
+            sale_type: listing.sale_type.to_string(),
+        });
+    };
+    if listing.status != ListingStatus::Active || env.block.time >= auction.commit_end {
+        return Err(ContractError::WrongAuctionPhase {
+            listing_id,
+            phase: "commitments".to_string(),
+        });
+    }
+    let min_deposit = auction.min_deposit as u128;
+    let config = CONFIG.load(deps.storage)?;
+    if !config.accepted_denoms.contains(&listing.denom) {
+        return Err(ContractError::InvalidDenom {
+            denom: listing.denom,
+        });
+    }
+    Ok((listing, min_deposit))
+}
+
+//escrows a sealed bidder's deposit against their commitment
+fn save_commitment(
+    deps: DepsMut,
+    env: &Env,
+    mut listing: Listing,
+    bidder: &Addr,
+    commitment: Binary,
+    payment: PaymentBreakdown,
+) -> Result<Response, ContractError> {
+    let listing_id = listing.listing_id;
+    if bidder.as_str() == listing.seller {
+        return Err(ContractError::Unauthorized {});
+    }
+    let key = (listing_id, bidder.clone());
+    if SEALED_BIDS.has(deps.storage, key.clone()) {
+        return Err(ContractError::AlreadyCommitted { listing_id });
+    }
+    let deposit = Uint64::try_from(Uint128::new(payment.received))
+        .map_err(StdError::from)?
+        .u64();
+    let SaleType::SealedBidAuction(auction) = &mut listing.sale_type else {
+        unreachable!("load_committable_listing only returns sealed bid auctions");
+    };
+    auction.commitment_count += 1;
+    auction.deposits_held += 1;
+    let sealed = SealedBid {
+        bidder: bidder.clone(),
+        commitment,
+        deposit,
+        committed_at: env.block.time,
+        amount: None,
+    };
+    SEALED_BIDS.save(deps.storage, key, &sealed)?;
+    LISTING.save(deps.storage, listing_id, &listing)?;
+    add_to_total(deps.storage, &ESCROWED, &listing.denom, deposit as u128)?;
+    Ok(Response::new()
+        .add_messages(payment.refund_msgs(bidder.as_str())?)
+        .add_attribute("action", "commit_bid")
+        .add_attribute("listing_id", listing_id.to_string())
+        .add_attribute("bidder", bidder.to_string())
+        .add_attribute("deposit", deposit.to_string()))
+}
+
+fn execute_commit_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+    commitment: Binary,
+) -> Result<Response, ContractError> {
+    let (listing, min_deposit) = load_committable_listing(deps.as_ref(), &env, listing_id)?;
+    //cw20 auctions take deposits through Receive
+    if cw20_contract(&listing.denom).is_some() {
+        return Err(ContractError::InvalidDenom {
+            denom: listing.denom,
+        });
+    }
+    let payment = check_bid(&info.funds, &listing.denom, min_deposit)?;
+    save_commitment(deps, &env, listing, &info.sender, commitment, payment)
+}
+
+//opens a commitment, a reveal at or above the reserve and within the deposit is a valid bid
+fn execute_reveal_bid(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+    amount: u64,
+    salt: String,
+) -> Result<Response, ContractError> {
+    let mut listing = LISTING.load(deps.storage, listing_id)?;
+    let SaleType::SealedBidAuction(auction) = &mut listing.sale_type else {
+        return Err(ContractError::WrongSaleType {
+            listing_id,
+            sale_type: listing.sale_type.to_string(),
+        });
+    };
+    if env.block.time < auction.commit_end || env.block.time >= auction.reveal_end {
+        return Err(ContractError::WrongAuctionPhase {
+            listing_id,
+            phase: "reveals".to_string(),
+        });
+    }
+    let key = (listing_id, info.sender.clone());
+    let mut sealed = SEALED_BIDS
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NoCommitment { listing_id })?;
+    if sealed.amount.is_some()
+        || sealed.commitment != sealed_bid_commitment(listing_id, &info.sender, amount, &salt)
+    {
+        return Err(ContractError::InvalidReveal {});
+    }
+    sealed.amount = Some(amount);
+    let valid = amount >= auction.reserve_price && amount <= sealed.deposit;
+    if valid {
+        auction.bid_count += 1;
+        let bid = Bid {
+            bid_id: auction.bid_count,
+            bidder: info.sender.clone(),
+            amount,
+            placed_at: env.block.time,
+        };
+        BIDS.save(deps.storage, (listing_id, bid.bid_id), &bid)?;
+        //ties go to the earlier reveal
+        if auction
+            .highest_bid
+            .as_ref()
+            .is_none_or(|highest| amount > highest.amount)
+        {
+            auction.highest_bid = Some(bid);
+        }
+    }
+    SEALED_BIDS.save(deps.storage, key, &sealed)?;
+    LISTING.save(deps.storage, listing_id, &listing)?;
+    Ok(Response::new()
+        .add_attribute("action", "reveal_bid")
+        .add_attribute("listing_id", listing_id.to_string())
+        .add_attribute("bidder", info.sender.to_string())
+        .add_attribute("amount", amount.to_string())
+        .add_attribute("valid", valid.to_string()))
+}
+
+//returns a deposit once reveals close, keeping forfeit_bps of it for the seller if it was never revealed
+fn execute_reclaim_deposit(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    listing_id: u64,
+) -> Result<Response, ContractError> {
+    let mut listing = LISTING.load(deps.storage, listing_id)?;
+    let SaleType::SealedBidAuction(auction) = &mut listing.sale_type else {
+        return Err(ContractError::WrongSaleType {
+            listing_id,
+            sale_type: listing.sale_type.to_string(),
+        });
+    };
+    if env.block.time < auction.reveal_end {
+        return Err(ContractError::AuctionNotEnded {
+            listing_id,
+            end_time: auction.reveal_end,
+        });
+    }
+    if auction
+        .highest_bid
+        .as_ref()
+        .is_some_and(|highest| highest.bidder == info.sender)
+    {
+        return Err(ContractError::WinningBid { listing_id });
+    }
+    let key = (listing_id, info.sender.clone());
+    let sealed = SEALED_BIDS
+        .may_load(deps.storage, key.clone())?
+        .ok_or(ContractError::NoCommitment { listing_id })?;
+    let forfeit = match sealed.amount {
+        Some(_) => 0,
+        None => sealed.deposit as u128 * auction.forfeit_bps as u128 / BPS_DENOMINATOR,
+    };
+    let refund = sealed.deposit as u128 - forfeit;
+    auction.deposits_held -= 1;
+    SEALED_BIDS.remove(deps.storage, key);
+    LISTING.save(deps.storage, listing_id, &listing)?;
+    let config = CONFIG.load(deps.storage)?;
+    subtract_from_total(
+        deps.storage,
+        &ESCROWED,
+        &listing.denom,
+        sealed.deposit as u128,
+    )?;
+    let mut msgs = payout(
+        &mut deps,
+        &config,
+        info.sender.as_str(),
+        refund,
+        &listing.denom,
+    )?;
+    if forfeit > 0 {
+        msgs.extend(payout(
+            &mut deps,
+            &config,
+            &listing.seller,
+            forfeit,
+            &listing.denom,
+        )?);
+    }
+    Ok(Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "reclaim_deposit")
+        .add_attribute("listing_id", listing_id.to_string())
+        .add_attribute("bidder", info.sender.to_string())
+        .add_attribute("refund", refund.to_string())
+        .add_attribute("forfeit", forfeit.to_string()))
+}
+//function allows the buyer to cancel a purchase if the purchase has not been shipped. It returns the funds to the buyer and the unit to stock.
+fn execute_cancel_purchase(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    if info.sender != order.buyer {
+        return Err(ContractError::Unauthorized {});
+    }
+    //once shipped only the seller or an arbiter can refund the buyer
+    if order.status != OrderStatus::Purchased {
+        return Err(ContractError::InvalidOrderTransition {
+            order_id,
+            from: order.status,
+            to: OrderStatus::Refunded,
+        });
+    }
+    transition_order(&mut order, OrderStatus::Refunded, env.block.time)?;
+    release_unit(deps.storage, &order, true)?;
+    let config = CONFIG.load(deps.storage)?;
+    release_escrow(deps.storage, &order)?;
+    let refund_msgs = payout(
+        &mut deps,
+        &config,
+        order.buyer.as_str(),
+        order.escrowed() as u128,
+        &order.denom,
+    )?;
+    refund_milestones(&mut order, env.block.time)?;
+    clear_settlements(deps.storage, &order)?;
+    ORDERS.save(deps.storage, order_id, &order)?;
+    Ok(Response::new()
+        .add_messages(refund_msgs)
+        .add_attribute("action", "cancel_purchase")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attributes([order_source(&order)]))
+}
+
+//Refunds an order whose seller let the ship by deadline pass, putting a listing's unit back on sale
+//and counting the failure on the seller's profile
+fn execute_expire_unshipped(
+    mut deps: DepsMut,
+    env: Env,
+    order_id: u64,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    //services count as shipped from their first delivered milestone
+    if order.status != OrderStatus::Purchased {
+        return Err(ContractError::InvalidOrderTransition {
+            order_id,
+            from: order.status,
+            to: OrderStatus::Refunded,
+        });
+    }
+    if env.block.time < order.ship_by {
+        return Err(ContractError::ShipByNotPassed {
+            order_id,
+            ship_by: order.ship_by,
+        });
+    }
+    transition_order(&mut order, OrderStatus::Refunded, env.block.time)?;
+    release_unit(deps.storage, &order, true)?;
+    let config = CONFIG.load(deps.storage)?;
+    release_escrow(deps.storage, &order)?;
+    let refund_msgs = payout(
+        &mut deps,
+        &config,
+        order.buyer.as_str(),
+        order.escrowed() as u128,
+        &order.denom,
+    )?;
+    refund_milestones(&mut order, env.block.time)?;
+    clear_settlements(deps.storage, &order)?;
+    ORDERS.save(deps.storage, order_id, &order)?;
+    if let Some(mut profile) = PROFILES.may_load(deps.storage, order.seller.clone())? {
+        profile.failed_shipments += 1;
+        PROFILES.save(deps.storage, order.seller.clone(), &profile)?;
+    }
+    Ok(Response::new()
+        .add_messages(refund_msgs)
+        .add_attribute("action", "expire_unshipped")
+        .add_attribute("order_id", order_id.to_string())
+        .add_attributes([order_source(&order)])
+        .add_attribute("seller", order.seller.to_string()))
+}
+
+//the arbiter's ruling settles the order either way, the unit is not put back in stock
+fn execute_arbitrate(
+    mut deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    order_id: u64,
+    ruling: RulingMsg,
+    milestone_id: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut order = ORDERS.load(deps.storage, order_id)?;
+    //ensure executor is an active arbiter
+    let config = CONFIG.load(deps.storage)?;
+    let arbiter = ARBITERS
+        .may_load(deps.storage, info.sender.clone())?
+        .filter(|arbiter| arbiter.active)
+        .ok_or(ContractError::Unauthorized {})?;
+    //the shares must account for the whole disputed amount
+    if ruling.buyer_bps as u128 + ruling.seller_bps as u128 + ruling.arbiter_bps as u128
+        != BPS_DENOMINATOR
+    {
+        return Err(ContractError::InvalidRuling {});
+    }
+    let fee_cap = arbiter.fee_bps.min(config.max_arbiter_fee_bps);
+    if ruling.arbiter_bps > fee_cap {
+        return Err(ContractError::ArbiterFeeTooHigh { fee_bps: fee_cap });
+    }
+    //one payout per party with a share of whatever is in dispute
+    let disputed = match milestone_id {
+        Some(milestone_id) => order.milestones[milestone_index(&order, milestone_id)?].amount,
+        None => order.escrowed(),
+    };
+    let amounts = split_by_bps(
+        disputed as u128,
+        &[ruling.buyer_bps, ruling.seller_bps, ruling.arbiter_bps],
+    );
+    //anything paid to the seller counts as a completed sale, a ruling has to pay one side or the other
+    let status = if amounts[1] > 0 {
+        OrderStatus::Completed
+    } else if amounts[0] > 0 {
+        OrderStatus::Refunded
+    } else {
+        return Err(ContractError::RulingPaysNoParty {});
+    };
+    let mut resp = Response::new()
+        .add_attribute("action", "arbitrate")
+        .add_attribute("order_id", order_id.to_string());
+    //ensure someone has requested arbitration
+    match milestone_id {
+        Some(milestone_id) => {
+            let milestone = &order.milestones[milestone_index(&order, milestone_id)?];
+            if milestone.status != MilestoneStatus::Disputed {
+                return Err(ContractError::InvalidMilestoneTransition {
+                    order_id,
+                    milestone_id,
+                    from: milestone.status,
+                    to: settled_milestone_status(status),
+                });
+            }
+        }
+        None if order.milestones.is_empty() && order.status != OrderStatus::Disputed => {
+            return Err(ContractError::InvalidOrderTransition {
+                order_id,
+                from: order.status,
+                to: status,
+            });
+        }
+        None => {}
+    }
+    let (_, attributes) = close_split(deps.storage, &env, &mut order, milestone_id, status)?;
+    resp = resp.add_attributes(attributes);
+    ORDERS.save(deps.storage, order_id, &order)?;
+
+    //the seller's share is a sale like any other and pays the marketplace fee, less its shipping
+    for (party, recipient, amount) in [
+        ("buyer", &order.buyer, amounts[0]),
+        ("arbiter", &info.sender, amounts[2]),
+    ] {
+        if amount > 0 {
+            let msgs = payout(&mut deps, &config, recipient.as_str(), amount, &order.denom)?;
+            resp = resp
+                .add_messages(msgs)
+                .add_attribute(format!("to {}", party), amount.to_string());
+        }
+    }
+    if amounts[1] > 0 {
+        let shipping = shipping_share(&order, milestone_id, amounts[1]);
+        let (msgs, attributes) = pay_seller(
+            &mut deps,
+            &config,
+            &order,
+            amounts[1] - shipping.u128(),
+            shipping,
+        )?;
+        resp = resp.add_messages(msgs).add_attributes(attributes);
+    }
+    let ruling = Ruling {
+        order_id,
+        milestone_id,
+        arbiter: info.sender,
+        buyer_bps: ruling.buyer_bps,
+        seller_bps: ruling.seller_bps,
+        arbiter_bps: ruling.arbiter_bps,
+        buyer_amount: amounts[0] as u64,
+        seller_amount: amounts[1] as u64,
+        arbiter_amount: amounts[2] as u64,
+        ruled_at: env.block.time,
+    };
+    RULINGS.save(deps.storage, (order_id, milestone_id.unwrap_or(0)), &ruling)?;
+    Ok(resp.add_attribute("status", order.status.to_string()))
+}
+
+//the part of a seller's share of a split that is shipping, pro rata, so the fee isn't charged on it
+fn shipping_share(order: &Order, milestone_id: Option<u64>, amount: u128) -> Uint128 {
+    if milestone_id.is_some() || order.shipping_price() == 0 {
+        return Uint128::zero();
+    }
+    Uint128::new(amount * order.shipping_price() as u128 / order.total() as u128)
+}
+
+//a milestone split in the seller's favour at all is released, otherwise refunded
+fn settled_milestone_status(status: OrderStatus) -> MilestoneStatus {
+    if status == OrderStatus::Completed {
+        MilestoneStatus::Released
+    } else {
+        MilestoneStatus::Refunded
+    }
+}
+
+//Settles an order, or one milestone of a service order, that is being split between the parties.
+//Returns the amount taken out of escrow for the split.
+fn close_split(
+    storage: &mut dyn Storage,
+    env: &Env,
+    order: &mut Order,
+    milestone_id: Option<u64>,
+    status: OrderStatus,
+) -> Result<(u64, Vec<Attribute>), ContractError> {
+    let Some(milestone_id) = milestone_id else {
+        if !order.milestones.is_empty() {
+            return Err(ContractError::ServiceOrder {
+                order_id: order.order_id,
+            });
+        }
+        transition_order(order, status, env.block.time)?;
+        release_unit(storage, order, false)?;
+        release_escrow(storage, order)?;
+        //a split with anything for the seller is a sale, the same as any other completion
+        if status == OrderStatus::Completed {
+            count_transaction(storage, order)?;
+        }
+        clear_settlements(storage, order)?;
+        return Ok((order.escrowed(), vec![]));
+    };
+    let index = milestone_index(order, milestone_id)?;
+    let to = settled_milestone_status(status);
+    transition_milestone(order, index, to, env.block.time)?;
+    let amount = order.milestones[index].amount;
+    subtract_from_total(storage, &ESCROWED, &order.denom, amount as u128)?;
+    finish_service_order(storage, order, env.block.time)?;
+    clear_settlements(storage, order)?;
+    Ok((
+        amount,
+        vec![
+            attr("milestone_id", milestone_id.to_string()),
+            attr("milestone_status", to.to_string()),
+        ],
+    ))
+}
+
+//escrows a requester's budget and indexes the ad under each of its tags
+#[allow(clippy::too_many_arguments)]
+fn save_wanted_ad(
+    deps: DepsMut,
+    env: &Env,
+    requester: &Addr,
+    title: String,
+    text: String,
+    tags: Vec<String>,
+    budget: u64,
+    payment: PaymentBreakdown,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.accepted_denoms.contains(&payment.denom) {
+        return Err(ContractError::InvalidDenom {
+            denom: payment.denom,
+        });
+    }
+    validate_title(&config, &title)?;
+    if text.len() as u64 > config.max_text_length {
+        return Err(ContractError::TooMuchText {});
+    }
+    if budget == 0 {
+        return Err(ContractError::InvalidProposalPrice { price: 0, budget });
+    }
+    let wanted_ad_id = LAST_WANTED_AD_ID.load(deps.storage)? + 1;
+    let wanted_ad = WantedAd {
+        wanted_ad_id,
+        requester: requester.clone(),
+        title,
+        text,
+        tags,
+        budget,
+        denom: payment.denom.clone(),
+        status: WantedAdStatus::Open,
+        created_at: env.block.time,
+        proposal_count: 0,
+        order_id: None,
+    };
+    LAST_WANTED_AD_ID.save(deps.storage, &wanted_ad_id)?;
+    WANTED_ADS.save(deps.storage, wanted_ad_id, &wanted_ad)?;
+    WANTED_ADS_BY_REQUESTER.save(deps.storage, (requester.clone(), wanted_ad_id), &())?;
+    for tag in &wanted_ad.tags {
+        WANTED_ADS_BY_TAG.save(deps.storage, (tag.clone(), wanted_ad_id), &())?;
+    }
+    add_to_total(deps.storage, &ESCROWED, &wanted_ad.denom, budget as u128)?;
+    Ok(Response::new()
+        .add_messages(payment.refund_msgs(requester.as_str())?)
+        .add_attribute("action", "post_wanted_ad")
+        .add_attribute("wanted_ad_id", wanted_ad_id.to_string())
+        .add_attribute("requester", requester.to_string())
+        .add_attribute("budget", budget.to_string())
+        .add_attributes(payment.attributes()))
+}
+
+//loads a wanted ad still taking proposals
+fn load_open_wanted_ad(
+    storage: &dyn Storage,
+    wanted_ad_id: u64,
+) -> Result<WantedAd, ContractError> {
+    let wanted_ad = WANTED_ADS.load(storage, wanted_ad_id)?;
+    if wanted_ad.status != WantedAdStatus::Open {
+        return Err(ContractError::WantedAdClosed {
+            wanted_ad_id,
+            status: wanted_ad.status,
+        });
+    }
+    Ok(wanted_ad)
+}
+
+//takes an open wanted ad out of the tag index once it is filled or cancelled
+fn close_wanted_ad(
+    storage: &mut dyn Storage,
+    wanted_ad: &mut WantedAd,
+    status: WantedAdStatus,
+) -> StdResult<()> {
+    wanted_ad.status = status;
+    for tag in &wanted_ad.tags {
+        WANTED_ADS_BY_TAG.remove(storage, (tag.clone(), wanted_ad.wanted_ad_id));
+    }
+    WANTED_ADS.save(storage, wanted_ad.wanted_ad_id, wanted_ad)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn execute_propose_fulfilment(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    wanted_ad_id: u64,
+    price: u64,
+    text: String,
+    external_id: Option<String>,
+    contact: String,
+) -> Result<Response, ContractError> {
+    let mut wanted_ad = load_open_wanted_ad(deps.storage, wanted_ad_id)?;
+    if info.sender == wanted_ad.requester {
+        return Err(ContractError::Unauthorized {});
     }
-    assert_sent_exact_coin(&info.funds, Some(vec![coin(listing.price as u128, ATOM)]))?;
-    listing.buyer = Some(info.sender.to_string());
-    listing.bought = true;
-    LISTING.save(deps.storage, listing_id, &listing)?;
+    if price == 0 || price > wanted_ad.budget {
+        return Err(ContractError::InvalidProposalPrice {
+            price,
+            budget: wanted_ad.budget,
+        });
+    }
+    let config = CONFIG.load(deps.storage)?;
+    if text.len() as u64 > config.max_text_length {
+        return Err(ContractError::TooMuchText {});
+    }
+    if let Some(external_id) = &external_id {
+        validate_external_id(&config, external_id)?;
+    }
+    validate_contact(&config, &contact)?;
+    wanted_ad.proposal_count += 1;
+    let proposal = Proposal {
+        proposal_id: wanted_ad.proposal_count,
+        seller: info.sender.clone(),
+        price,
+        text,
+        external_id,
+        contact,
+        submitted_at: env.block.time,
+    };
+    PROPOSALS.save(
+        deps.storage,
+        (wanted_ad_id, proposal.proposal_id),
+        &proposal,
+    )?;
+    WANTED_ADS.save(deps.storage, wanted_ad_id, &wanted_ad)?;
     Ok(Response::new()
-        .add_attribute("action", "purchase")
-        .add_attribute("post_id", listing_id.to_string())
-        .add_attribute("buyer", info.sender.to_string()))
+        .add_attribute("action", "propose_fulfilment")
+        .add_attribute("wanted_ad_id", wanted_ad_id.to_string())
+        .add_attribute("proposal_id", proposal.proposal_id.to_string())
+        .add_attribute("seller", info.sender.to_string())
+        .add_attribute("price", price.to_string()))
 }
-//function allows the buyer to cancel a purchase if the purchase has not been shipped. It returns the funds to the buyer.
-fn execute_cancel_purchase(
-    deps: DepsMut,
-    _env: Env,
+
+//the chosen proposal becomes an order paid from the budget, what's left of the budget is refunded
+fn execute_accept_proposal(
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    listing_id: u64,
+    wanted_ad_id: u64,
+    proposal_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut listing = LISTING.load(deps.storage, listing_id)?;
-    if !listing.bought || listing.shipped {
-        return Err(ContractError::NotEligibleForCancellation {});
-    }
-    if Some(info.sender.to_string()) != listing.buyer {
+    let mut wanted_ad = load_open_wanted_ad(deps.storage, wanted_ad_id)?;
+    if info.sender != wanted_ad.requester {
         return Err(ContractError::Unauthorized {});
     }
-    let bank_msg = BankMsg::Send {
-        to_address: listing.buyer.unwrap(),
-        amount: vec![coin(listing.price as u128, ATOM)],
+    let proposal = PROPOSALS.load(deps.storage, (wanted_ad_id, proposal_id))?;
+    let order = Order {
+        order_id: LAST_ORDER_ID.load(deps.storage)? + 1,
+        listing_id: None,
+        wanted_ad_id: Some(wanted_ad_id),
+        buyer: wanted_ad.requester.clone(),
+        seller: proposal.seller,
+        variant_id: None,
+        price: proposal.price,
+        denom: wanted_ad.denom.clone(),
+        shipping: None,
+        status: OrderStatus::Purchased,
+        purchased_at: env.block.time,
+        ship_by: env
+            .block
+            .time
+            .plus_seconds(CONFIG.load(deps.storage)?.ship_window),
+        shipped_at: None,
+        disputed_at: None,
+        completed_at: None,
+        refunded_at: None,
+        confirm_by: None,
+        shipment_id: None,
+        milestones: vec![],
     };
-    listing.bought = false;
-    listing.buyer = None;
-    LISTING.save(deps.storage, listing_id, &listing)?;
+    save_new_order(deps.storage, &order)?;
+    wanted_ad.order_id = Some(order.order_id);
+    close_wanted_ad(deps.storage, &mut wanted_ad, WantedAdStatus::Filled)?;
+    let excess = (wanted_ad.budget - proposal.price) as u128;
+    let mut refund_msgs = vec![];
+    if excess > 0 {
+        let config = CONFIG.load(deps.storage)?;
+        subtract_from_total(deps.storage, &ESCROWED, &wanted_ad.denom, excess)?;
+        refund_msgs = payout(
+            &mut deps,
+            &config,
+            wanted_ad.requester.as_str(),
+            excess,
+            &wanted_ad.denom,
+        )?;
+    }
     Ok(Response::new()
-        .add_message(bank_msg)
-        .add_attribute("action", "cancel_purchase")
-        .add_attribute("listing_id", listing_id.to_string()))
+        .add_messages(refund_msgs)
+        .add_attribute("action", "accept_proposal")
+        .add_attribute("wanted_ad_id", wanted_ad_id.to_string())
+        .add_attribute("proposal_id", proposal_id.to_string())
+        .add_attribute("order_id", order.order_id.to_string()))
 }
 
-fn execute_arbitrate(
-    deps: DepsMut,
+fn execute_cancel_wanted_ad(
+    mut deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    listing_id: u64,
-    funds_recipient: String,
+    wanted_ad_id: u64,
 ) -> Result<Response, ContractError> {
-    let listing = LISTING.load(deps.storage, listing_id)?;
-    //ensure someone has requested arbitration
-    if !listing.arbitration_requested {
-        return Err(ContractError::ArbitrationNotRequested {});
-    }
-    //ensure executor is an arbiter
-    if !is_arbiter(&deps, info.sender.as_ref()) {
+    let mut wanted_ad = load_open_wanted_ad(deps.storage, wanted_ad_id)?;
+    if info.sender != wanted_ad.requester {
         return Err(ContractError::Unauthorized {});
     }
-    //ensure funds recipient is either the seller or the buyer to prevent fraud
-    if funds_recipient != listing.seller && funds_recipient != listing.buyer.unwrap() {
-        return Err(ContractError::InvalidFundsRecipient {});
-    }
-    let bank_msg = BankMsg::Send {
-        to_address: funds_recipient,
-        amount: vec![coin(listing.price as u128, ATOM)],
-    };
-    //remove listing from state
-    LISTING.remove(deps.storage, listing_id);
-    //save decremented counter
-    let counter = LISTING_COUNT.load(deps.storage)?;
-    let updated_counter = counter - 1;
-    LISTING_COUNT.save(deps.storage, &updated_counter)?;
+    close_wanted_ad(deps.storage, &mut wanted_ad, WantedAdStatus::Cancelled)?;
+    let config = CONFIG.load(deps.storage)?;
+    subtract_from_total(
+        deps.storage,
+        &ESCROWED,
+        &wanted_ad.denom,
+        wanted_ad.budget as u128,
+    )?;
+    let refund_msgs = payout(
+        &mut deps,
+        &config,
+        wanted_ad.requester.as_str(),
+        wanted_ad.budget as u128,
+        &wanted_ad.denom,
+    )?;
     Ok(Response::new()
-        .add_message(bank_msg)
-        .add_attribute("action", "arbitrate")
-        .add_attribute("post_id", listing_id.to_string()))
+        .add_messages(refund_msgs)
+        .add_attribute("action", "cancel_wanted_ad")
+        .add_attribute("wanted_ad_id", wanted_ad_id.to_string()))
 }
 
 pub fn execute_delete_profile(
@@ -473,7 +3390,7 @@ pub fn execute_delete_profile(
     // Check if profile exists
     let profile_name = PROFILE_NAME.may_load(deps.storage, info.sender.clone())?;
     
-    if let Some(_) = profile_name {
+    if let Some(profile_name) = profile_name {
         // Remove profile name mapping
         PROFILE_NAME.remove(deps.storage, info.sender.clone());
         
@@ -483,51 +3400,64 @@ pub fn execute_delete_profile(
         Ok(Response::new()
             .add_attribute("action", "delete_profile")
             .add_attribute("address", info.sender)
-            .add_attribute("profile_name", profile_name.unwrap()))
+            .add_attribute("profile_name", profile_name))
     } else {
         Err(ContractError::ProfileNotFound {})
     }
 }
 
 fn execute_seller_cancel_sale(
-    deps: DepsMut,
-    _env: Env,
+    mut deps: DepsMut,
+    env: Env,
     info: MessageInfo,
-    listing_id: u64,
+    order_id: u64,
 ) -> Result<Response, ContractError> {
-    let mut listing = LISTING.load(deps.storage, listing_id)?;
+    let mut order = ORDERS.load(deps.storage, order_id)?;
     
     // Verify the executor is the seller
-    if info.sender.to_string() != listing.seller {
+    if info.sender != order.seller {
         return Err(ContractError::Unauthorized {});
     }
 
-    // Check if the listing is actually purchased
-    if !listing.bought {
-        return Err(ContractError::NotPurchased {});
-    }
-
-    let bank_msg = BankMsg::Send {
-        to_address: listing.buyer.clone().unwrap(),
-        amount: vec![coin(listing.price as u128, ATOM)],
+    // Refund the buyer and put the unit back in stock, a service only refunds its unsettled milestones
+    // and counts as completed if the seller was already paid for any
+    let paid_out = order
+        .milestones
+        .iter()
+        .any(|milestone| milestone.status == MilestoneStatus::Released);
+    let status = if paid_out {
+        OrderStatus::Completed
+    } else {
+        OrderStatus::Refunded
     };
+    transition_order(&mut order, status, env.block.time)?;
+    release_unit(deps.storage, &order, !paid_out)?;
+    let config = CONFIG.load(deps.storage)?;
+    release_escrow(deps.storage, &order)?;
+    let refund_amount = order.escrowed();
+    let refund_msgs = payout(
+        &mut deps,
+        &config,
+        order.buyer.as_str(),
+        refund_amount as u128,
+        &order.denom,
+    )?;
+    refund_milestones(&mut order, env.block.time)?;
+    if paid_out {
+        count_transaction(deps.storage, &order)?;
+    }
+    clear_settlements(deps.storage, &order)?;
 
-    // Reset purchase-related fields
-    listing.bought = false;
-    listing.buyer = None;
-    listing.shipped = false;
-    listing.received = false;
-    listing.arbitration_requested = false;
-
-    // Save updated listing
-    LISTING.save(deps.storage, listing_id, &listing)?;
+    // Save updated order
+    ORDERS.save(deps.storage, order_id, &order)?;
 
     Ok(Response::new()
-        .add_message(bank_msg)
+        .add_messages(refund_msgs)
         .add_attribute("action", "seller_cancel_sale")
-        .add_attribute("listing_id", listing_id.to_string())
-        .add_attribute("refunded_buyer", listing.buyer.unwrap())
-        .add_attribute("refund_amount", listing.price.to_string()))
+        .add_attribute("order_id", order_id.to_string())
+        .add_attributes([order_source(&order)])
+        .add_attribute("refunded_buyer", order.buyer.to_string())
+        .add_attribute("refund_amount", refund_amount.to_string()))
 }
 
 fn execute_rate_user(
@@ -538,7 +3468,7 @@ fn execute_rate_user(
     rating: u64,
 ) -> Result<Response, ContractError> {
     // Validate rating is between 1 and 5
-    if rating < 1 || rating > 5 {
+    if !(1..=5).contains(&rating) {
         return Err(ContractError::InvalidRating {});
     }
 
@@ -586,7 +3516,7 @@ fn execute_cleanup_old_relationships(
 
     // Get all relationships
     let relationships: Vec<_> = RELATIONSHIPS
-        .range(deps.storage, None, None, Order::Ascending)
+        .range(deps.storage, None, None, StorageOrder::Ascending)
         .collect::<StdResult<Vec<_>>>()?;
 
     // Process each relationship
@@ -606,6 +3536,298 @@ fn execute_cleanup_old_relationships(
         .add_attribute("relationships_deleted", deleted_count.to_string()))
 }
 
+//Every changed field is emitted as an old_ and new_ attribute pair
+#[allow(clippy::too_many_arguments)]
+fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    fee_bps: Option<u64>,
+    fee_recipients: Option<Vec<FeeRecipientMsg>>,
+    accepted_denoms: Option<Vec<String>>,
+    gateway: Option<String>,
+    max_text_length: Option<u64>,
+    max_id_length: Option<u64>,
+    push_payouts: Option<bool>,
+    confirmation_window: Option<u64>,
+    ship_window: Option<u64>,
+    max_arbiter_fee_bps: Option<u64>,
+) -> Result<Response, ContractError> {
+    let mut config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut resp = Response::new().add_attribute("action", "update_config");
+    if let Some(fee_bps) = fee_bps {
+        if fee_bps as u128 > BPS_DENOMINATOR {
+            return Err(ContractError::InvalidFeeSchedule {});
+        }
+        resp = resp
+            .add_attribute("old_fee_bps", config.fee_bps.to_string())
+            .add_attribute("new_fee_bps", fee_bps.to_string());
+        config.fee_bps = fee_bps;
+    }
+    if let Some(fee_recipients) = fee_recipients {
+        let fee_recipients = validate_fee_schedule(deps.api, config.fee_bps, fee_recipients)?;
+        resp = resp
+            .add_attribute(
+                "old_fee_recipients",
+                fee_recipients_to_string(&config.fee_recipients),
+            )
+            .add_attribute(
+                "new_fee_recipients",
+                fee_recipients_to_string(&fee_recipients),
+            );
+        config.fee_recipients = fee_recipients;
+    }
+    if let Some(accepted_denoms) = accepted_denoms {
+        validate_denoms(deps.api, &accepted_denoms)?;
+        resp = resp
+            .add_attribute("old_accepted_denoms", config.accepted_denoms.join(","))
+            .add_attribute("new_accepted_denoms", accepted_denoms.join(","));
+        config.accepted_denoms = accepted_denoms;
+    }
+    if let Some(gateway) = gateway {
+        resp = resp
+            .add_attribute("old_gateway", config.gateway)
+            .add_attribute("new_gateway", gateway.clone());
+        config.gateway = gateway;
+    }
+    if let Some(max_text_length) = max_text_length {
+        resp = resp
+            .add_attribute("old_max_text_length", config.max_text_length.to_string())
+            .add_attribute("new_max_text_length", max_text_length.to_string());
+        config.max_text_length = max_text_length;
+    }
+    if let Some(max_id_length) = max_id_length {
+        resp = resp
+            .add_attribute("old_max_id_length", config.max_id_length.to_string())
+            .add_attribute("new_max_id_length", max_id_length.to_string());
+        config.max_id_length = max_id_length;
+    }
+    if let Some(push_payouts) = push_payouts {
+        resp = resp
+            .add_attribute("old_push_payouts", config.push_payouts.to_string())
+            .add_attribute("new_push_payouts", push_payouts.to_string());
+        config.push_payouts = push_payouts;
+    }
+    if let Some(confirmation_window) = confirmation_window {
+        validate_window("confirmation_window", confirmation_window)?;
+        resp = resp
+            .add_attribute(
+                "old_confirmation_window",
+                config.confirmation_window.to_string(),
+            )
+            .add_attribute("new_confirmation_window", confirmation_window.to_string());
+        config.confirmation_window = confirmation_window;
+    }
+    if let Some(ship_window) = ship_window {
+        validate_window("ship_window", ship_window)?;
+        resp = resp
+            .add_attribute("old_ship_window", config.ship_window.to_string())
+            .add_attribute("new_ship_window", ship_window.to_string());
+        config.ship_window = ship_window;
+    }
+    //arbiters registered above a lowered cap are held to it when they rule
+    if let Some(max_arbiter_fee_bps) = max_arbiter_fee_bps {
+        if max_arbiter_fee_bps as u128 > BPS_DENOMINATOR {
+            return Err(ContractError::InvalidFeeSchedule {});
+        }
+        resp = resp
+            .add_attribute(
+                "old_max_arbiter_fee_bps",
+                config.max_arbiter_fee_bps.to_string(),
+            )
+            .add_attribute("new_max_arbiter_fee_bps", max_arbiter_fee_bps.to_string());
+        config.max_arbiter_fee_bps = max_arbiter_fee_bps;
+    }
+    CONFIG.save(deps.storage, &config)?;
+    Ok(resp)
+}
+
+//sends the sender's credited payouts in `denom`, everything when amount is not set
+fn execute_withdraw(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    denom: String,
+    amount: Option<Uint128>,
+) -> Result<Response, ContractError> {
+    let key = (info.sender.clone(), denom.clone());
+    let balance = BALANCES
+        .may_load(deps.storage, key.clone())?
+        .unwrap_or_default();
+    let amount = amount.unwrap_or(balance);
+    if amount.is_zero() || amount > balance {
+        return Err(ContractError::InsufficientBalance {
+            requested: format!("{}{}", amount, denom),
+            available: format!("{}{}", balance, denom),
+        });
+    }
+    let remaining = balance - amount;
+    if remaining.is_zero() {
+        BALANCES.remove(deps.storage, key);
+    } else {
+        BALANCES.save(deps.storage, key, &remaining)?;
+    }
+    subtract_from_total(deps.storage, &OWED, &denom, amount.u128())?;
+    Ok(Response::new()
+        .add_message(send_tokens(info.sender.as_str(), amount.u128(), &denom)?)
+        .add_attribute("action", "withdraw")
+        .add_attribute("recipient", info.sender.to_string())
+        .add_attribute("denom", denom)
+        .add_attribute("amount", amount)
+        .add_attribute("remaining", remaining))
+}
+
+fn execute_sweep_excess(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    denom: String,
+    recipient: Option<String>,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(&recipient)?,
+        None => config.admin,
+    };
+    let contract_balance = contract_balance(deps.as_ref(), &env, &denom)?;
+    let summary = escrow_summary(deps.as_ref(), denom.clone(), contract_balance)?;
+    if summary.excess.is_zero() {
+        return Err(ContractError::NothingToSweep { denom });
+    }
+    Ok(Response::new()
+        .add_message(send_tokens(
+            recipient.as_str(),
+            summary.excess.u128(),
+            &denom,
+        )?)
+        .add_attribute("action", "sweep_excess")
+        .add_attribute("recipient", recipient.to_string())
+        .add_attribute("denom", denom)
+        .add_attribute("amount", summary.excess)
+        .add_attribute("escrowed", summary.escrowed)
+        .add_attribute("owed", summary.owed))
+}
+
+//registers or updates an arbiter, always leaving it active
+fn save_arbiter(
+    storage: &mut dyn Storage,
+    api: &dyn Api,
+    max_arbiter_fee_bps: u64,
+    arbiter: ArbiterMsg,
+) -> Result<Arbiter, ContractError> {
+    if arbiter.fee_bps > max_arbiter_fee_bps {
+        return Err(ContractError::ArbiterFeeTooHigh {
+            fee_bps: max_arbiter_fee_bps,
+        });
+    }
+    let arbiter = Arbiter {
+        address: api.addr_validate(&arbiter.address)?,
+        name: arbiter.name,
+        fee_bps: arbiter.fee_bps,
+        active: true,
+    };
+    ARBITERS.save(storage, arbiter.address.clone(), &arbiter)?;
+    Ok(arbiter)
+}
+
+fn execute_add_arbiter(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+    name: String,
+    fee_bps: u64,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let arbiter = save_arbiter(
+        deps.storage,
+        deps.api,
+        config.max_arbiter_fee_bps,
+        ArbiterMsg {
+            address,
+            name,
+            fee_bps,
+        },
+    )?;
+    Ok(Response::new()
+        .add_attribute("action", "add_arbiter")
+        .add_attribute("arbiter", arbiter.address.to_string())
+        .add_attribute("name", arbiter.name)
+        .add_attribute("fee_bps", arbiter.fee_bps.to_string()))
+}
+
+//deactivates rather than deletes, orders already shipped or ruled on by the arbiter keep pointing at it
+fn execute_remove_arbiter(
+    deps: DepsMut,
+    info: MessageInfo,
+    address: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let address = deps.api.addr_validate(&address)?;
+    let mut arbiter = ARBITERS.load(deps.storage, address.clone())?;
+    arbiter.active = false;
+    ARBITERS.save(deps.storage, address.clone(), &arbiter)?;
+    Ok(Response::new()
+        .add_attribute("action", "remove_arbiter")
+        .add_attribute("arbiter", address.to_string()))
+}
+
+//first half of the admin transfer, the proposed address must accept before it takes effect
+fn execute_propose_new_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    new_admin: String,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    if info.sender != config.admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let new_admin = deps.api.addr_validate(&new_admin)?;
+    let old_pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
+    PENDING_ADMIN.save(deps.storage, &new_admin)?;
+    Ok(Response::new()
+        .add_attribute("action", "propose_new_admin")
+        .add_attribute(
+            "old_pending_admin",
+            old_pending_admin.map_or("none".to_string(), |admin| admin.to_string()),
+        )
+        .add_attribute("new_pending_admin", new_admin.to_string()))
+}
+
+fn execute_accept_admin(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let pending_admin = PENDING_ADMIN
+        .may_load(deps.storage)?
+        .ok_or(ContractError::NoPendingAdmin {})?;
+    if info.sender != pending_admin {
+        return Err(ContractError::Unauthorized {});
+    }
+    let mut config = CONFIG.load(deps.storage)?;
+    let old_admin = config.admin;
+    config.admin = pending_admin;
+    CONFIG.save(deps.storage, &config)?;
+    PENDING_ADMIN.remove(deps.storage);
+    Ok(Response::new()
+        .add_attribute("action", "accept_admin")
+        .add_attribute("old_admin", old_admin.to_string())
+        .add_attribute("new_admin", config.admin.to_string()))
+}
+
 #[entry_point]
 pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
@@ -613,6 +3835,7 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             query_all_listings(deps, env, limit, start_after)
         }
         QueryMsg::Listing { listing_id } => query_listing(deps, env, listing_id),
+        QueryMsg::CurrentPrice { listing_id } => query_current_price(deps, env, listing_id),
         QueryMsg::ListingCount {} => query_listing_count(deps, env),
         QueryMsg::ArbitrationListings { limit, start_after } => {
             query_arbitration_listings(deps, env, limit, start_after)
@@ -621,6 +3844,48 @@ pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
             query_listings_by_title(deps, title, limit)
         }
         QueryMsg::Profile { address } => query_profile(deps, address),
+        QueryMsg::Config {} => query_config(deps),
+        QueryMsg::Arbiters { limit, start_after } => query_arbiters(deps, limit, start_after),
+        QueryMsg::Arbiter { address } => query_arbiter(deps, address),
+        QueryMsg::FeePreview { price } => query_fee_preview(deps, price),
+        QueryMsg::Balance { address } => query_balance(deps, address),
+        QueryMsg::EscrowSummary {} => query_escrow_summary(deps, env),
+        QueryMsg::Order { order_id } => query_order(deps, order_id),
+        QueryMsg::Rulings { order_id } => query_rulings(deps, order_id),
+        QueryMsg::Settlements { order_id } => query_settlements(deps, order_id),
+        QueryMsg::OrdersByAddress {
+            address,
+            limit,
+            start_after,
+        } => query_orders_by_address(deps, address, limit, start_after),
+        QueryMsg::Bids {
+            listing_id,
+            limit,
+            start_after,
+        } => query_bids(deps, listing_id, limit, start_after),
+        QueryMsg::SealedBid { listing_id, bidder } => query_sealed_bid(deps, listing_id, bidder),
+        QueryMsg::Offer { offer_id } => query_offer(deps, offer_id),
+        QueryMsg::WantedAd { wanted_ad_id } => query_wanted_ad(deps, wanted_ad_id),
+        QueryMsg::WantedAdsByTag {
+            tag,
+            limit,
+            start_after,
+        } => query_wanted_ads_by_tag(deps, tag, limit, start_after),
+        QueryMsg::WantedAdsByRequester {
+            requester,
+            limit,
+            start_after,
+        } => query_wanted_ads_by_requester(deps, requester, limit, start_after),
+        QueryMsg::Proposals {
+            wanted_ad_id,
+            limit,
+            start_after,
+        } => query_proposals(deps, wanted_ad_id, limit, start_after),
+        QueryMsg::OffersByListing {
+            listing_id,
+            limit,
+            start_after,
+        } => query_offers_by_listing(deps, listing_id, limit, start_after),
     }
 }
 
@@ -637,7 +3902,7 @@ fn query_all_listings(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
     let listings = LISTING
-        .range(deps.storage, None, start, Order::Descending)
+        .range(deps.storage, None, start, StorageOrder::Descending)
         .take(limit)
         .map(|p| Ok(p?.1))
         .collect::<StdResult<Vec<_>>>()?;
@@ -648,6 +3913,15 @@ fn query_listing(deps: Deps, _env: Env, listing_id: u64) -> StdResult<Binary> {
     let listing = LISTING.may_load(deps.storage, listing_id)?;
     to_json_binary(&ListingResponse { listing })
 }
+fn query_current_price(deps: Deps, env: Env, listing_id: u64) -> StdResult<Binary> {
+    let listing = LISTING.load(deps.storage, listing_id)?;
+    to_json_binary(&CurrentPriceResponse {
+        listing_id,
+        price: current_price(&listing, env.block.time),
+        denom: listing.denom,
+    })
+}
+
 fn query_listing_count(deps: Deps, _env: Env) -> StdResult<Binary> {
     let listing_count = LISTING_COUNT.load(deps.storage)?;
     to_json_binary(&ListingCountResponse { listing_count })
@@ -662,24 +3936,173 @@ fn query_arbitration_listings(
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
     let start = start_after.map(Bound::exclusive);
 
-    let listings: Vec<Listing> = LISTING
-        .range(deps.storage, None, start, Order::Descending)
+    let orders: Vec<Order> = ORDERS
+        .range(deps.storage, None, start, StorageOrder::Descending)
         .filter(|item| match item {
-            Ok((_, listing)) => listing.arbitration_requested,
+            Ok((_, order)) => {
+                order.status == OrderStatus::Disputed
+                    || order
+                        .milestones
+                        .iter()
+                        .any(|milestone| milestone.status == MilestoneStatus::Disputed)
+            }
             Err(_) => false,
         })
         .take(limit)
-        .map(|item| item.map(|(_, listing)| listing))
+        .map(|item| item.map(|(_, order)| order))
+        .collect::<StdResult<Vec<_>>>()?;
+
+    to_json_binary(&ArbitrationListingsResponse { orders })
+}
+
+fn query_order(deps: Deps, order_id: u64) -> StdResult<Binary> {
+    let order = ORDERS.may_load(deps.storage, order_id)?;
+    to_json_binary(&OrderResponse { order })
+}
+
+fn query_rulings(deps: Deps, order_id: u64) -> StdResult<Binary> {
+    let rulings = RULINGS
+        .prefix(order_id)
+        .range(deps.storage, None, None, StorageOrder::Ascending)
+        .map(|item| item.map(|(_, ruling)| ruling))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&RulingsResponse { rulings })
+}
+
+fn query_settlements(deps: Deps, order_id: u64) -> StdResult<Binary> {
+    let settlements = SETTLEMENTS
+        .prefix(order_id)
+        .range(deps.storage, None, None, StorageOrder::Ascending)
+        .map(|item| item.map(|(_, settlement)| settlement))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&SettlementsResponse { settlements })
+}
+
+fn query_orders_by_address(
+    deps: Deps,
+    address: String,
+    limit: Option<u32>,
+    start_after: Option<u64>,
+) -> StdResult<Binary> {
+    let addr = deps.api.addr_validate(&address)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let orders = ORDERS_BY_ADDRESS
+        .prefix(addr)
+        .keys(deps.storage, None, start, StorageOrder::Descending)
+        .take(limit)
+        .map(|order_id| ORDERS.load(deps.storage, order_id?))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&OrdersResponse { orders })
+}
+
+fn query_bids(
+    deps: Deps,
+    listing_id: u64,
+    limit: Option<u32>,
+    start_after: Option<u64>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let bids = BIDS
+        .prefix(listing_id)
+        .range(deps.storage, None, start, StorageOrder::Descending)
+        .take(limit)
+        .map(|item| item.map(|(_, bid)| bid))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&BidsResponse { bids })
+}
+
+fn query_sealed_bid(deps: Deps, listing_id: u64, bidder: String) -> StdResult<Binary> {
+    let bidder = deps.api.addr_validate(&bidder)?;
+    let bid = SEALED_BIDS.may_load(deps.storage, (listing_id, bidder))?;
+    to_json_binary(&SealedBidResponse { bid })
+}
+
+fn query_offer(deps: Deps, offer_id: u64) -> StdResult<Binary> {
+    let offer = OFFERS.may_load(deps.storage, offer_id)?;
+    to_json_binary(&OfferResponse { offer })
+}
+
+fn query_offers_by_listing(
+    deps: Deps,
+    listing_id: u64,
+    limit: Option<u32>,
+    start_after: Option<u64>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let offers = OPEN_OFFERS
+        .prefix(listing_id)
+        .keys(deps.storage, start, None, StorageOrder::Ascending)
+        .take(limit)
+        .map(|offer_id| OFFERS.load(deps.storage, offer_id?))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&OffersResponse { offers })
+}
+
+fn query_wanted_ad(deps: Deps, wanted_ad_id: u64) -> StdResult<Binary> {
+    let wanted_ad = WANTED_ADS.may_load(deps.storage, wanted_ad_id)?;
+    to_json_binary(&WantedAdResponse { wanted_ad })
+}
+
+fn query_wanted_ads_by_tag(
+    deps: Deps,
+    tag: String,
+    limit: Option<u32>,
+    start_after: Option<u64>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let wanted_ads = WANTED_ADS_BY_TAG
+        .prefix(tag)
+        .keys(deps.storage, None, start, StorageOrder::Descending)
+        .take(limit)
+        .map(|wanted_ad_id| WANTED_ADS.load(deps.storage, wanted_ad_id?))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&WantedAdsResponse { wanted_ads })
+}
+
+fn query_wanted_ads_by_requester(
+    deps: Deps,
+    requester: String,
+    limit: Option<u32>,
+    start_after: Option<u64>,
+) -> StdResult<Binary> {
+    let requester = deps.api.addr_validate(&requester)?;
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let wanted_ads = WANTED_ADS_BY_REQUESTER
+        .prefix(requester)
+        .keys(deps.storage, None, start, StorageOrder::Descending)
+        .take(limit)
+        .map(|wanted_ad_id| WANTED_ADS.load(deps.storage, wanted_ad_id?))
         .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&WantedAdsResponse { wanted_ads })
+}
 
-    to_json_binary(&ArbitrationListingsResponse { listings })
+fn query_proposals(
+    deps: Deps,
+    wanted_ad_id: u64,
+    limit: Option<u32>,
+    start_after: Option<u64>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(Bound::exclusive);
+    let proposals = PROPOSALS
+        .prefix(wanted_ad_id)
+        .range(deps.storage, start, None, StorageOrder::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, proposal)| proposal))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&ProposalsResponse { proposals })
 }
 
 fn query_listings_by_title(deps: Deps, title: String, limit: Option<u32>) -> StdResult<Binary> {
     let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
 
     let listings: Vec<Listing> = LISTING_TITLES
-        .range(deps.storage, None, None, Order::Ascending)
+        .range(deps.storage, None, None, StorageOrder::Ascending)
         .filter(|item| {
             if let Ok((stored_title, _)) = item {
                 stored_title.to_lowercase().contains(&title.to_lowercase())
@@ -704,7 +4127,7 @@ fn query_profile(deps: Deps, address: String) -> StdResult<Binary> {
     let addr = deps.api.addr_validate(&address)?;
     let profile_name = PROFILE_NAME.may_load(deps.storage, addr.clone())?;
     
-    if let Some(_) = profile_name {
+    if profile_name.is_some() {
         let profile = PROFILES.load(deps.storage, addr)?;
         to_json_binary(&ProfileResponse { profile: Some(profile) })
     } else {
@@ -712,12 +4135,154 @@ fn query_profile(deps: Deps, address: String) -> StdResult<Binary> {
     }
 }
 
+fn query_arbiters(
+    deps: Deps,
+    limit: Option<u32>,
+    start_after: Option<String>,
+) -> StdResult<Binary> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after
+        .map(|address| deps.api.addr_validate(&address))
+        .transpose()?
+        .map(Bound::exclusive);
+    let arbiters = ARBITERS
+        .range(deps.storage, start, None, StorageOrder::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, arbiter)| arbiter))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&ArbitersResponse { arbiters })
+}
+
+fn query_arbiter(deps: Deps, address: String) -> StdResult<Binary> {
+    let address = deps.api.addr_validate(&address)?;
+    let arbiter = ARBITERS.may_load(deps.storage, address)?;
+    to_json_binary(&ArbiterResponse { arbiter })
+}
+
+fn query_config(deps: Deps) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let pending_admin = PENDING_ADMIN.may_load(deps.storage)?;
+    to_json_binary(&ConfigResponse {
+        config,
+        pending_admin,
+    })
+}
+
+fn query_fee_preview(deps: Deps, price: u64) -> StdResult<Binary> {
+    let config = CONFIG.load(deps.storage)?;
+    let split = split_fee(&config, price as u128);
+    to_json_binary(&FeePreviewResponse { price, split })
+}
+
+fn query_balance(deps: Deps, address: String) -> StdResult<Binary> {
+    let addr = deps.api.addr_validate(&address)?;
+    let balances = BALANCES
+        .prefix(addr)
+        .range(deps.storage, None, None, StorageOrder::Ascending)
+        .map(|item| item.map(|(denom, amount)| Coin { denom, amount }))
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&BalanceResponse { address, balances })
+}
+
+//what the contract holds of `denom`, from the bank module or the cw20 token contract
+fn contract_balance(deps: Deps, env: &Env, denom: &str) -> StdResult<Uint128> {
+    match cw20_contract(denom) {
+        Some(token_contract) => {
+            let res: Cw20BalanceResponse = deps.querier.query_wasm_smart(
+                token_contract,
+                &Cw20QueryMsg::Balance {
+                    address: env.contract.address.to_string(),
+                },
+            )?;
+            Ok(res.balance)
+        }
+        None => Ok(deps
+            .querier
+            .query_balance(&env.contract.address, denom)?
+            .amount),
+    }
+}
+
+fn escrow_summary(
+    deps: Deps,
+    denom: String,
+    contract_balance: Uint128,
+) -> StdResult<DenomEscrowSummary> {
+    let escrowed = ESCROWED
+        .may_load(deps.storage, denom.clone())?
+        .unwrap_or_default();
+    let owed = OWED
+        .may_load(deps.storage, denom.clone())?
+        .unwrap_or_default();
+    let committed = escrowed + owed;
+    Ok(DenomEscrowSummary {
+        denom,
+        contract_balance,
+        escrowed,
+        owed,
+        excess: contract_balance.saturating_sub(committed),
+        shortfall: committed.saturating_sub(contract_balance),
+    })
+}
+
+fn query_escrow_summary(deps: Deps, env: Env) -> StdResult<Binary> {
+    //the full balance list is the only way to spot native denoms nobody escrowed
+    #[allow(deprecated)]
+    let native_balances = deps.querier.query_all_balances(&env.contract.address)?;
+    let config = CONFIG.load(deps.storage)?;
+    let mut denoms: Vec<String> = native_balances.iter().map(|c| c.denom.clone()).collect();
+    for totals in [&ESCROWED, &OWED] {
+        for denom in totals.keys(deps.storage, None, None, StorageOrder::Ascending) {
+            denoms.push(denom?);
+        }
+    }
+    denoms.extend(
+        config
+            .accepted_denoms
+            .into_iter()
+            .filter(|denom| cw20_contract(denom).is_some()),
+    );
+    denoms.sort();
+    denoms.dedup();
+    let denoms = denoms
+        .into_iter()
+        .map(|denom| {
+            let contract_balance = match cw20_contract(&denom) {
+                Some(_) => contract_balance(deps, &env, &denom)?,
+                None => native_balances
+                    .iter()
+                    .find(|c| c.denom == denom)
+                    .map_or(Uint128::zero(), |c| c.amount),
+            };
+            escrow_summary(deps, denom, contract_balance)
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    to_json_binary(&EscrowSummaryResponse { denoms })
+}
+
+//splits "1.2.3" into [1, 2, 3] so versions compare numerically
+fn version_parts(version: &str) -> Vec<u64> {
+    version
+        .split('.')
+        .map(|part| part.parse().unwrap_or(0))
+        .collect()
+}
+
 #[entry_point]
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
     let ver = get_contract_version(deps.storage)?;
     if ver.contract != CONTRACT_NAME {
         return Err(StdError::generic_err("Can only upgrade from same type").into());
     }
+    //0.1.0 moved sales into ORDERS and grew Config and Listing, older state can't be loaded by
+    //this code so those deployments need a fresh instantiate instead
+    if version_parts(&ver.version) < version_parts(FIRST_ORDERS_VERSION) {
+        return Err(StdError::generic_err(format!(
+            "Cannot migrate from {}, state written before {} is not compatible",
+            ver.version, FIRST_ORDERS_VERSION
+        ))
+        .into());
+    }
     //canonical way from official docs https://docs.cosmwasm.com/docs/1.0/smart-contracts/migration/#migrate-which-updates-the-version-only-if-newer
     #[allow(clippy::cmp_owned)]
     if ver.version > (*CONTRACT_VERSION).to_string() {
@@ -1,4 +1,6 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Timestamp};
+
+use crate::state::{ListingStatus, MilestoneStatus, OfferStatus, OrderStatus, WantedAdStatus};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -15,29 +17,197 @@ pub enum ContractError {
     #[error("Only One Link Allowed")]
     OnlyOneLink {},
 
-    #[error("Seller must sign shipped prior to buyer signing received")]
-    NotShipped {},
+    #[error("Titles must be between 1 and {max_length} characters")]
+    InvalidTitle { max_length: u64 },
+
+    #[error("Contact details must be between 1 and {max_length} characters")]
+    InvalidContact { max_length: u64 },
+
+    #[error("Listing {listing_id} cannot go from {from} to {to}")]
+    InvalidStatusTransition {
+        listing_id: u64,
+        from: ListingStatus,
+        to: ListingStatus,
+    },
+
+    #[error("Listing {listing_id} is sold out")]
+    SoldOut { listing_id: u64 },
+
+    #[error("Listing {listing_id} has {reserved} units reserved by orders in escrow")]
+    OpenOrders { listing_id: u64, reserved: u64 },
+
+    #[error("Variant {variant_id} of listing {listing_id} is sold out")]
+    VariantSoldOut { listing_id: u64, variant_id: u64 },
+
+    #[error("Listing {listing_id} has variants, a variant_id is required")]
+    VariantRequired { listing_id: u64 },
+
+    #[error("Listing {listing_id} has no variant {variant_id}")]
+    InvalidVariant { listing_id: u64, variant_id: u64 },
+
+    #[error("Listing {listing_id} is a {sale_type} listing")]
+    WrongSaleType { listing_id: u64, sale_type: String },
+
+    #[error("Auction needs an end time in the future and a minimum increment of at least 1")]
+    InvalidAuction {},
+
+    #[error("Price schedule needs a start price above the floor and an end time in the future")]
+    InvalidPriceSchedule {},
+
+    #[error("Sealed bid auction needs a commit phase ending in the future, a reveal phase after it and a forfeit of at most 10000 bps")]
+    InvalidSealedBidAuction {},
+
+    #[error("Auction for listing {listing_id} is not taking {phase} right now")]
+    WrongAuctionPhase { listing_id: u64, phase: String },
+
+    #[error("Already committed a bid on listing {listing_id}")]
+    AlreadyCommitted { listing_id: u64 },
+
+    #[error("No sealed bid from this address on listing {listing_id}")]
+    NoCommitment { listing_id: u64 },
+
+    #[error("Reveal doesn't match an unrevealed commitment")]
+    InvalidReveal {},
+
+    #[error("The winning bid on listing {listing_id} is paid out through SettleAuction")]
+    WinningBid { listing_id: u64 },
+
+    #[error("Auction for listing {listing_id} has ended")]
+    AuctionEnded { listing_id: u64 },
+
+    #[error("Auction for listing {listing_id} runs until {end_time}")]
+    AuctionNotEnded {
+        listing_id: u64,
+        end_time: Timestamp,
+    },
+
+    #[error("Auction for listing {listing_id} already has bids")]
+    AuctionHasBids { listing_id: u64 },
+
+    #[error("Quantity must be at least 1")]
+    InvalidQuantity {},
+
+    #[error("Order {order_id} cannot go from {from} to {to}")]
+    InvalidOrderTransition {
+        order_id: u64,
+        from: OrderStatus,
+        to: OrderStatus,
+    },
+
+    #[error("Offer price {price} must be between {min} and {max}")]
+    InvalidOfferPrice { price: u64, min: u64, max: u64 },
+
+    #[error("Expiry must be in the future")]
+    InvalidExpiry {},
+
+    #[error("Offer {offer_id} has expired")]
+    OfferExpired { offer_id: u64 },
 
-    #[error("Not eligible for arbitration")]
-    NotEligibleForArbitration {},
+    #[error("Offer {offer_id} is already {status}")]
+    OfferClosed { offer_id: u64, status: OfferStatus },
 
-    #[error("Not eligible for cancellation")]
-    NotEligibleForCancellation {},
+    #[error("Wanted ad {wanted_ad_id} is already {status}")]
+    WantedAdClosed {
+        wanted_ad_id: u64,
+        status: WantedAdStatus,
+    },
 
-    #[error("Arbitration not requested by seller or buyer")]
-    ArbitrationNotRequested {},
+    #[error("Proposal price {price} must be between 1 and the budget of {budget}")]
+    InvalidProposalPrice { price: u64, budget: u64 },
 
-    #[error("This listing is already purchased by another customer")]
-    AlreadyPurchased {},
+    #[error("Listing {listing_id} needs a shipping option")]
+    ShippingRequired { listing_id: u64 },
+
+    #[error("Listing {listing_id} has no shipping option {shipping_id}")]
+    InvalidShipping { listing_id: u64, shipping_id: u64 },
+
+    #[error("Listing {listing_id} doesn't ship to {region}")]
+    NoShippingToRegion { listing_id: u64, region: String },
+
+    #[error("A service needs at least one milestone, each with a non-zero amount")]
+    InvalidMilestones {},
+
+    #[error("Order {order_id} is settled milestone by milestone")]
+    ServiceOrder { order_id: u64 },
+
+    #[error("Order {order_id} has no milestone {milestone_id}")]
+    InvalidMilestone { order_id: u64, milestone_id: u64 },
+
+    #[error("Milestone {milestone_id} of order {order_id} can't be delivered before the ones ahead of it")]
+    MilestoneOutOfOrder { order_id: u64, milestone_id: u64 },
+
+    #[error("Milestone {milestone_id} of order {order_id} cannot go from {from} to {to}")]
+    InvalidMilestoneTransition {
+        order_id: u64,
+        milestone_id: u64,
+        from: MilestoneStatus,
+        to: MilestoneStatus,
+    },
+
+    #[error("Order {order_id} can't be claimed before {confirm_by}")]
+    ConfirmationWindowOpen {
+        order_id: u64,
+        confirm_by: Timestamp,
+    },
+
+    #[error(
+        "Order {order_id} was shipped without a confirmation window, the buyer has to sign for it"
+    )]
+    NoConfirmationWindow { order_id: u64 },
+
+    #[error("{window} must be at least one second")]
+    InvalidWindow { window: String },
+
+    #[error("Order {order_id} can't be expired before {ship_by}")]
+    ShipByNotPassed { order_id: u64, ship_by: Timestamp },
+
+    #[error("The buyer's share can't be more than 10000 basis points")]
+    InvalidSettlementShare {},
+
+    #[error("Order {order_id} has no settlement proposal")]
+    NoSettlement { order_id: u64 },
+
+    #[error("The settlement proposed for order {order_id} gives the buyer {buyer_share_bps} basis points")]
+    SettlementChanged { order_id: u64, buyer_share_bps: u64 },
+
+    #[error("Cart is empty")]
+    EmptyCart {},
+
+    #[error("Every listing in a cart must be priced in {expected}, found {denom}")]
+    MixedCartDenoms { expected: String, denom: String },
+
+    #[error("This action doesn't take a payment")]
+    UnexpectedFunds {},
 
     #[error("Insufficient funds. Needed: {needed} Sent: {received}")]
     NotEnoughFunds { needed: String, received: String },
 
-    #[error("Funds recipient must be the seller or buyer")]
-    InvalidFundsRecipient {},
+    #[error("Ruling shares must add up to 10000 basis points")]
+    InvalidRuling {},
+
+    #[error("A ruling has to pay the buyer or the seller part of the disputed amount")]
+    RulingPaysNoParty {},
 
-    #[error("The IPFS link must be with Julian's dedicated gateway: https://julian.infura-ipfs.io/ipfs/")]
-    MustUseJulianGateway {},
+    #[error("This arbiter's fee is capped at {fee_bps} basis points")]
+    ArbiterFeeTooHigh { fee_bps: u64 },
+
+    #[error("The IPFS link must be with Julian's dedicated gateway: {gateway}")]
+    MustUseJulianGateway { gateway: String },
+
+    #[error("Fee must be at most 10000 bps and every fee recipient needs a non-zero weight")]
+    InvalidFeeSchedule {},
+
+    #[error("No admin transfer has been proposed")]
+    NoPendingAdmin {},
+
+    #[error("Nothing to sweep for {denom}")]
+    NothingToSweep { denom: String },
+
+    #[error("Insufficient balance. Requested: {requested} Available: {available}")]
+    InsufficientBalance {
+        requested: String,
+        available: String,
+    },
 
     #[error("The profile name {taken_profile_name} is already taken. Please choose another")]
     ProfileNameTaken { taken_profile_name: String },
@@ -54,9 +224,6 @@ pub enum ContractError {
     #[error("Profile not found")]
     ProfileNotFound {},
 
-    #[error("Item cannot be cancelled because it has not been purchased")]
-    NotPurchased {},
-
     #[error("Rating must be between 1 and 5")]
     InvalidRating {},
 
@@ -0,0 +1,121 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::{Addr, Api, Uint128};
+
+use crate::error::ContractError;
+use crate::msg::FeeRecipientMsg;
+use crate::state::{Config, FeeRecipient};
+
+//fees are expressed in basis points, 10_000 bps = 100%
+pub const BPS_DENOMINATOR: u128 = 10_000;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeShare {
+    pub name: String,
+    pub address: Addr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeSplit {
+    pub seller_amount: Uint128,
+    pub fee_amount: Uint128,
+    pub shares: Vec<FeeShare>,
+}
+
+//validates a fee schedule, an empty recipient list is kept empty and paid to the admin of the day
+pub fn validate_fee_schedule(
+    api: &dyn Api,
+    fee_bps: u64,
+    fee_recipients: Vec<FeeRecipientMsg>,
+) -> Result<Vec<FeeRecipient>, ContractError> {
+    if fee_bps as u128 > BPS_DENOMINATOR {
+        return Err(ContractError::InvalidFeeSchedule {});
+    }
+    fee_recipients
+        .into_iter()
+        .map(|recipient| {
+            if recipient.weight == 0 {
+                return Err(ContractError::InvalidFeeSchedule {});
+            }
+            Ok(FeeRecipient {
+                name: recipient.name,
+                address: api.addr_validate(&recipient.address)?,
+                weight: recipient.weight,
+            })
+        })
+        .collect()
+}
+
+//The fee is rounded down in the seller's favour. Each recipient gets its weighted share rounded
+//down and whatever dust is left over goes to the first recipient, so the split is deterministic.
+pub fn split_fee(config: &Config, price: u128) -> FeeSplit {
+    let fee_amount = price * config.fee_bps as u128 / BPS_DENOMINATOR;
+    let fee_recipients = resolve_fee_recipients(config);
+    let total_weight: u128 = fee_recipients
+        .iter()
+        .map(|recipient| recipient.weight as u128)
+        .sum();
+    let mut shares: Vec<FeeShare> = fee_recipients
+        .iter()
+        .map(|recipient| FeeShare {
+            name: recipient.name.clone(),
+            address: recipient.address.clone(),
+            amount: Uint128::new(fee_amount * recipient.weight as u128 / total_weight),
+        })
+        .collect();
+    let distributed: u128 = shares.iter().map(|share| share.amount.u128()).sum();
+    if let Some(first) = shares.first_mut() {
+        first.amount += Uint128::new(fee_amount - distributed);
+    }
+    FeeSplit {
+        seller_amount: Uint128::new(price - fee_amount),
+        fee_amount: Uint128::new(fee_amount),
+        shares,
+    }
+}
+
+//Splits an amount by basis point shares. Each share is rounded down and the dust goes to the first
+//party with a share, so nobody ruled out of a split is paid.
+pub fn split_by_bps(amount: u128, shares: &[u64]) -> Vec<u128> {
+    let mut amounts: Vec<u128> = shares
+        .iter()
+        .map(|bps| amount * *bps as u128 / BPS_DENOMINATOR)
+        .collect();
+    let distributed: u128 = amounts.iter().sum();
+    if let Some(index) = shares.iter().position(|bps| *bps > 0) {
+        amounts[index] += amount - distributed;
+    }
+    amounts
+}
+
+//who the fee is paid to right now, the current admin when no recipients are configured so an
+//admin transfer moves the fee along with it
+fn resolve_fee_recipients(config: &Config) -> Vec<FeeRecipient> {
+    if config.fee_recipients.is_empty() {
+        return vec![FeeRecipient {
+            name: "admin".to_string(),
+            address: config.admin.clone(),
+            weight: 1,
+        }];
+    }
+    config.fee_recipients.clone()
+}
+
+//renders a fee schedule for old_/new_ config attributes
+pub fn fee_recipients_to_string(fee_recipients: &[FeeRecipient]) -> String {
+    if fee_recipients.is_empty() {
+        return "admin".to_string();
+    }
+    fee_recipients
+        .iter()
+        .map(|recipient| {
+            format!(
+                "{}:{}:{}",
+                recipient.name, recipient.address, recipient.weight
+            )
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
@@ -4,5 +4,8 @@ pub mod msg;
 pub mod state;
 pub use crate::error::ContractError;
 pub mod coin_helpers;
+pub mod fees;
+#[cfg(test)]
+mod multitest;
 #[cfg(test)]
 mod tests;
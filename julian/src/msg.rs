@@ -1,13 +1,88 @@
-use crate::state::{Listing, Profile};
+use crate::fees::FeeSplit;
+use crate::state::{
+    Arbiter, Bid, Config, Listing, Offer, Order, Profile, Proposal, Ruling, SealedBid, Settlement,
+    WantedAd,
+};
+use cosmwasm_std::{Addr, Binary, Coin, Timestamp, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
-pub struct InstantiateMsg {}
+pub struct InstantiateMsg {
+    //defaults to the instantiator when not set
+    pub admin: Option<String>,
+    pub arbiters: Vec<ArbiterMsg>,
+    pub fee_bps: u64,
+    //the whole fee goes to the current admin when empty, following any later admin transfer
+    pub fee_recipients: Vec<FeeRecipientMsg>,
+    //native denoms, or cw20 tokens as "cw20:<token contract>"
+    pub accepted_denoms: Vec<String>,
+    pub gateway: String,
+    //pay sellers, fee recipients and refunds directly instead of through Withdraw
+    pub push_payouts: bool,
+    //seconds after shipment before an unconfirmed order can be claimed for the seller
+    pub confirmation_window: u64,
+    //seconds after purchase before an unshipped order can be refunded to the buyer
+    pub ship_window: u64,
+    //caps every arbiter's fee_bps
+    pub max_arbiter_fee_bps: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct VariantMsg {
+    pub name: String,
+    pub price: u64,
+    pub quantity: u64,
+    pub external_id: Option<String>,
+}
+
+//basis point shares of a disputed amount, adding up to 10_000
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RulingMsg {
+    pub buyer_bps: u64,
+    pub seller_bps: u64,
+    //paid to the arbiter making the ruling
+    pub arbiter_bps: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ShippingOptionMsg {
+    pub name: String,
+    pub price: u64,
+    pub regions: Vec<String>,
+    pub estimated_days: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct MilestoneMsg {
+    pub description: String,
+    pub amount: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ArbiterMsg {
+    pub address: String,
+    pub name: String,
+    pub fee_bps: u64,
+}
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub struct FeeRecipientMsg {
+    pub name: String,
+    pub address: String,
+    pub weight: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     CreateListing {
         listing_title: String,
@@ -16,6 +91,74 @@ pub enum ExecuteMsg {
         tags: Vec<String>,
         contact: String,
         price: u64,
+        denom: String,
+        //units for sale, each purchase buys one
+        quantity: u64,
+        //when not empty, price and quantity come from the variants instead
+        variants: Vec<VariantMsg>,
+        //buyers pick one at purchase when there are any
+        shipping_options: Vec<ShippingOptionMsg>,
+        //seconds to ship each order, the configured ship window when not set
+        ship_within: Option<u64>,
+    },
+    //lists a single unit for auction, priced in denom
+    CreateAuction {
+        listing_title: String,
+        external_id: String,
+        text: String,
+        tags: Vec<String>,
+        contact: String,
+        denom: String,
+        start_price: u64,
+        //0 for no reserve
+        reserve_price: u64,
+        min_increment: u64,
+        end_time: Timestamp,
+        //bids this close to end_time extend the auction, 0 turns extensions off
+        extension_seconds: u64,
+    },
+    //lists units whose price falls from start_price to floor_price by end_time, starting now
+    CreateDutchAuction {
+        listing_title: String,
+        external_id: String,
+        text: String,
+        tags: Vec<String>,
+        contact: String,
+        denom: String,
+        quantity: u64,
+        start_price: u64,
+        floor_price: u64,
+        end_time: Timestamp,
+        //0 for a linear drop
+        step_seconds: u64,
+    },
+    //lists a single unit for a commit-reveal auction, priced in denom
+    CreateSealedBidAuction {
+        listing_title: String,
+        external_id: String,
+        text: String,
+        tags: Vec<String>,
+        contact: String,
+        denom: String,
+        min_deposit: u64,
+        //0 for no reserve
+        reserve_price: u64,
+        commit_end: Timestamp,
+        reveal_end: Timestamp,
+        //share of an unrevealed deposit forfeited to the seller, in basis points
+        forfeit_bps: u64,
+    },
+    //lists a service bought through Purchase for the sum of its milestones, paid to the seller
+    //one milestone at a time as the buyer approves each delivery
+    CreateServiceListing {
+        listing_title: String,
+        external_id: String,
+        text: String,
+        tags: Vec<String>,
+        contact: String,
+        denom: String,
+        quantity: u64,
+        milestones: Vec<MilestoneMsg>,
     },
     EditListing {
         listing_id: u64,
@@ -23,31 +166,169 @@ pub enum ExecuteMsg {
         text: String,
         tags: Vec<String>,
         price: u64,
+        //replaces the variants when set, keeps them otherwise
+        variants: Option<Vec<VariantMsg>>,
+        //same for the shipping options
+        shipping_options: Option<Vec<ShippingOptionMsg>>,
+        //replaces the listing's ship window when set
+        ship_within: Option<u64>,
     },
     DeleteListing {
         listing_id: u64,
     },
+    //adds units to a listing, putting a sold out listing back on sale
+    RestockListing {
+        listing_id: u64,
+        quantity: u64,
+        //required for listings with variants
+        variant_id: Option<u64>,
+    },
     Purchase {
         listing_id: u64,
+        //required for listings with variants
+        variant_id: Option<u64>,
+        //required for listings with shipping options
+        shipping_id: Option<u64>,
     },
-    CancelPurchase {
+    //buys one unit of every listing in one payment, all or nothing. Listings with variants are
+    //bought through Purchase
+    PurchaseMany {
+        listing_ids: Vec<u64>,
+        //orders from the same seller share one SignShipped
+        group_by_seller: bool,
+        //listings with shipping options ship with their cheapest option to this region
+        region: Option<String>,
+    },
+    //everything sent in the listing's denom is the bid
+    PlaceBid {
         listing_id: u64,
     },
-    SellerCancelSale {
+    //sealed bid auctions: the funds sent are the deposit, commitment is
+    //sha256("<listing_id>:<bidder>:<amount>:<salt>") and the amount can't exceed the deposit
+    CommitBid {
         listing_id: u64,
+        commitment: Binary,
     },
-    SignShipped {
+    RevealBid {
         listing_id: u64,
+        amount: u64,
+        salt: String,
     },
-    SignReceived {
+    //after the reveal phase, returns a losing or unrevealed deposit less any forfeit
+    ReclaimDeposit {
         listing_id: u64,
     },
-    RequestArbitration {
+    //escrows an offer below the list price, the funds sent must cover price
+    MakeOffer {
         listing_id: u64,
+        variant_id: Option<u64>,
+        //escrowed with the offer at the option's price, it isn't haggled over
+        shipping_id: Option<u64>,
+        price: u64,
+        expires_at: Timestamp,
     },
-    Arbitrate {
+    //the seller accepts the buyer's price, or the buyer accepts a counter by sending the difference
+    AcceptOffer {
+        offer_id: u64,
+    },
+    RejectOffer {
+        offer_id: u64,
+    },
+    CounterOffer {
+        offer_id: u64,
+        price: u64,
+    },
+    //the buyer takes an offer back, anyone can once it has expired
+    WithdrawOffer {
+        offer_id: u64,
+    },
+    //anyone can close an auction once it has ended, the winning bid becomes an order
+    SettleAuction {
         listing_id: u64,
-        funds_recipient: String,
+    },
+    //escrows a budget for an item the sender is looking for, the funds sent must cover it
+    PostWantedAd {
+        title: String,
+        text: String,
+        tags: Vec<String>,
+        denom: String,
+        budget: u64,
+    },
+    ProposeFulfilment {
+        wanted_ad_id: u64,
+        price: u64,
+        text: String,
+        external_id: Option<String>,
+        contact: String,
+    },
+    //the requester picks a proposal, opening an order at its price and refunding the rest of the budget
+    AcceptProposal {
+        wanted_ad_id: u64,
+        proposal_id: u64,
+    },
+    CancelWantedAd {
+        wanted_ad_id: u64,
+    },
+    //the escrow steps below act on the order created by Purchase, PurchaseMany, SettleAuction,
+    //AcceptOffer or AcceptProposal
+    CancelPurchase {
+        order_id: u64,
+    },
+    SellerCancelSale {
+        order_id: u64,
+    },
+    SignShipped {
+        order_id: u64,
+    },
+    SignReceived {
+        order_id: u64,
+    },
+    //anyone can refund the buyer of an order the seller didn't ship in time
+    ExpireUnshipped {
+        order_id: u64,
+    },
+    //anyone can release a shipped order to the seller once its confirmation window has passed
+    ClaimAfterTimeout {
+        order_id: u64,
+    },
+    //service orders: the seller delivers milestones in order and the buyer approves each one,
+    //releasing its amount less the fee
+    DeliverMilestone {
+        order_id: u64,
+        milestone_id: u64,
+    },
+    ApproveMilestone {
+        order_id: u64,
+        milestone_id: u64,
+    },
+    //milestone_id is required for service orders, where it disputes just that milestone
+    RequestArbitration {
+        order_id: u64,
+        milestone_id: Option<u64>,
+    },
+    //either side of a shipped order can offer to split its escrow, replacing any earlier proposal,
+    //milestone_id works as in RequestArbitration
+    ProposeSettlement {
+        order_id: u64,
+        milestone_id: Option<u64>,
+        buyer_share_bps: u64,
+    },
+    //the other side settles the split straight away, ending any dispute, buyer_share_bps must
+    //match the proposal
+    AcceptSettlement {
+        order_id: u64,
+        milestone_id: Option<u64>,
+        buyer_share_bps: u64,
+    },
+    RevokeSettlement {
+        order_id: u64,
+        milestone_id: Option<u64>,
+    },
+    //splits the disputed order, or just the milestone for service orders, between the parties
+    Arbitrate {
+        order_id: u64,
+        ruling: RulingMsg,
+        milestone_id: Option<u64>,
     },
     CreateProfile {
         profile_name: String,
@@ -58,6 +339,84 @@ pub enum ExecuteMsg {
         rating: u64,
     },
     CleanupOldRelationships {},
+    UpdateConfig {
+        fee_bps: Option<u64>,
+        fee_recipients: Option<Vec<FeeRecipientMsg>>,
+        accepted_denoms: Option<Vec<String>>,
+        gateway: Option<String>,
+        max_text_length: Option<u64>,
+        max_id_length: Option<u64>,
+        push_payouts: Option<bool>,
+        confirmation_window: Option<u64>,
+        ship_window: Option<u64>,
+        max_arbiter_fee_bps: Option<u64>,
+    },
+    ProposeNewAdmin {
+        new_admin: String,
+    },
+    AcceptAdmin {},
+    //cw20 token contracts call this when a buyer sends tokens with a ReceiveMsg attached
+    Receive(Cw20ReceiveMsg),
+    //withdraws credited payouts, the whole balance of the denom when amount is not set
+    Withdraw {
+        denom: String,
+        amount: Option<Uint128>,
+    },
+    //admin only, sends whatever the contract holds beyond escrow and owed payouts
+    SweepExcess {
+        denom: String,
+        //defaults to the admin
+        recipient: Option<String>,
+    },
+    //admin only, registers an arbiter or updates one and makes it active again
+    AddArbiter {
+        address: String,
+        name: String,
+        fee_bps: u64,
+    },
+    //admin only, the arbiter stays in the registry as inactive
+    RemoveArbiter {
+        address: String,
+    },
+}
+//hook messages carried in Cw20ReceiveMsg.msg
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ReceiveMsg {
+    Purchase {
+        listing_id: u64,
+        variant_id: Option<u64>,
+        shipping_id: Option<u64>,
+    },
+    PurchaseMany {
+        listing_ids: Vec<u64>,
+        group_by_seller: bool,
+        region: Option<String>,
+    },
+    PlaceBid {
+        listing_id: u64,
+    },
+    CommitBid {
+        listing_id: u64,
+        commitment: Binary,
+    },
+    MakeOffer {
+        listing_id: u64,
+        variant_id: Option<u64>,
+        shipping_id: Option<u64>,
+        price: u64,
+        expires_at: Timestamp,
+    },
+    //tops up a countered offer to the counter price
+    AcceptOffer {
+        offer_id: u64,
+    },
+    PostWantedAd {
+        title: String,
+        text: String,
+        tags: Vec<String>,
+        budget: u64,
+    },
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct ProfileResponse {
@@ -81,7 +440,70 @@ pub struct ListingCountResponse {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct ArbitrationListingsResponse {
-    pub listings: Vec<Listing>,
+    //disputed orders waiting on an arbiter
+    pub orders: Vec<Order>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OrderResponse {
+    pub order: Option<Order>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct RulingsResponse {
+    pub rulings: Vec<Ruling>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SettlementsResponse {
+    pub settlements: Vec<Settlement>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OrdersResponse {
+    pub orders: Vec<Order>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BidsResponse {
+    pub bids: Vec<Bid>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct CurrentPriceResponse {
+    pub listing_id: u64,
+    pub price: u64,
+    pub denom: String,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct SealedBidResponse {
+    pub bid: Option<SealedBid>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OfferResponse {
+    pub offer: Option<Offer>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct OffersResponse {
+    pub offers: Vec<Offer>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WantedAdResponse {
+    pub wanted_ad: Option<WantedAd>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct WantedAdsResponse {
+    pub wanted_ads: Vec<WantedAd>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ProposalsResponse {
+    pub proposals: Vec<Proposal>,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
@@ -90,6 +512,51 @@ pub struct SearchListingsResponse {
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
+pub struct ConfigResponse {
+    pub config: Config,
+    pub pending_admin: Option<Addr>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ArbitersResponse {
+    pub arbiters: Vec<Arbiter>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct ArbiterResponse {
+    pub arbiter: Option<Arbiter>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct FeePreviewResponse {
+    pub price: u64,
+    pub split: FeeSplit,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct BalanceResponse {
+    pub address: String,
+    pub balances: Vec<Coin>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct DenomEscrowSummary {
+    pub denom: String,
+    pub contract_balance: Uint128,
+    pub escrowed: Uint128,
+    pub owed: Uint128,
+    //contract_balance - escrowed - owed, what SweepExcess can withdraw
+    pub excess: Uint128,
+    //how far contract_balance falls short of escrowed + owed, should always be zero
+    pub shortfall: Uint128,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub struct EscrowSummaryResponse {
+    pub denoms: Vec<DenomEscrowSummary>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     AllListings {
         limit: Option<u32>,
@@ -98,7 +565,12 @@ pub enum QueryMsg {
     Listing {
         listing_id: u64,
     },
+    //what Purchase would charge right now, following the schedule for dutch auctions
+    CurrentPrice {
+        listing_id: u64,
+    },
     ListingCount {},
+    //start_after is an order id
     ArbitrationListings {
         limit: Option<u32>,
         start_after: Option<u64>,
@@ -110,10 +582,84 @@ pub enum QueryMsg {
     Profile {
         address: String,
     },
+    Config {},
+    //registered arbiters, active or not, ordered by address
+    Arbiters {
+        limit: Option<u32>,
+        start_after: Option<String>,
+    },
+    Arbiter {
+        address: String,
+    },
+    //seller proceeds and each fee share for a sale at this price
+    FeePreview {
+        price: u64,
+    },
+    //payouts credited to an address and not yet withdrawn
+    Balance {
+        address: String,
+    },
+    EscrowSummary {},
+    Order {
+        order_id: u64,
+    },
+    //the rulings made on an order, the whole order's or one per disputed milestone
+    Rulings {
+        order_id: u64,
+    },
+    //settlement proposals still open on an order
+    Settlements {
+        order_id: u64,
+    },
+    //orders the address bought or sold, newest first
+    OrdersByAddress {
+        address: String,
+        limit: Option<u32>,
+        start_after: Option<u64>,
+    },
+    //bids placed on an auction, newest first, start_after is a bid id
+    Bids {
+        listing_id: u64,
+        limit: Option<u32>,
+        start_after: Option<u64>,
+    },
+    Offer {
+        offer_id: u64,
+    },
+    //open and countered offers on a listing, oldest first, start_after is an offer id
+    OffersByListing {
+        listing_id: u64,
+        limit: Option<u32>,
+        start_after: Option<u64>,
+    },
+    WantedAd {
+        wanted_ad_id: u64,
+    },
+    //open wanted ads with the tag, newest first, start_after is a wanted ad id
+    WantedAdsByTag {
+        tag: String,
+        limit: Option<u32>,
+        start_after: Option<u64>,
+    },
+    //every wanted ad the address posted, newest first
+    WantedAdsByRequester {
+        requester: String,
+        limit: Option<u32>,
+        start_after: Option<u64>,
+    },
+    //proposals on a wanted ad, oldest first, start_after is a proposal id
+    Proposals {
+        wanted_ad_id: u64,
+        limit: Option<u32>,
+        start_after: Option<u64>,
+    },
+    //a bidder's commitment on a sealed bid auction
+    SealedBid {
+        listing_id: u64,
+        bidder: String,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub struct MigrateMsg {}
-
-
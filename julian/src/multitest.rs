@@ -0,0 +1,531 @@
+//End to end tests that need a real chain around the contract (cw20 tokens, bank balances)
+use crate::contract::{execute, instantiate, query};
+use crate::msg::{
+    ArbiterMsg, EscrowSummaryResponse, ExecuteMsg, InstantiateMsg, OrderResponse, QueryMsg,
+    ReceiveMsg, RulingMsg,
+};
+use cosmwasm_std::{to_json_binary, Addr, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Cw20QueryMsg};
+use cw_multi_test::error::AnyResult;
+use cw_multi_test::{App, ContractWrapper, Executor};
+
+const IPFS: &str = "https://gateway.pinata.cloud/ipfs/";
+const IPFS_LINK: &str =
+    "https://gateway.pinata.cloud/ipfs/QmQSXMeJRyodyVESWVXT8gd7kQhjrV7sguLnsrXSd6YzvT";
+
+//cw20-base token, instantiated with `balance` for each address
+fn instantiate_token(app: &mut App, owner: &Addr, label: &str, balances: &[(&Addr, u128)]) -> Addr {
+    let token_code = app.store_code(Box::new(ContractWrapper::new(
+        cw20_base::contract::execute,
+        cw20_base::contract::instantiate,
+        cw20_base::contract::query,
+    )));
+    app.instantiate_contract(
+        token_code,
+        owner.clone(),
+        &cw20_base::msg::InstantiateMsg {
+            name: "Julian Test Token".to_string(),
+            symbol: "JTT".to_string(),
+            decimals: 6,
+            initial_balances: balances
+                .iter()
+                .map(|(address, amount)| Cw20Coin {
+                    address: address.to_string(),
+                    amount: Uint128::new(*amount),
+                })
+                .collect(),
+            mint: None,
+            marketing: None,
+        },
+        &[],
+        label,
+        None,
+    )
+    .unwrap()
+}
+
+struct Suite {
+    app: App,
+    julian: Addr,
+    token: Addr,
+    admin: Addr,
+    seller: Addr,
+    buyer: Addr,
+}
+
+//instantiates a cw20 token funding the buyer and a Julian contract accepting it
+fn setup(push_payouts: bool) -> Suite {
+    let mut app = App::default();
+    let admin = app.api().addr_make("admin");
+    let seller = app.api().addr_make("seller");
+    let buyer = app.api().addr_make("buyer");
+
+    let token = instantiate_token(&mut app, &admin, "token", &[(&buyer, 1_000_000)]);
+
+    let julian_code = app.store_code(Box::new(ContractWrapper::new(execute, instantiate, query)));
+    let julian = app
+        .instantiate_contract(
+            julian_code,
+            admin.clone(),
+            &InstantiateMsg {
+                admin: None,
+                arbiters: vec![ArbiterMsg {
+                    address: admin.to_string(),
+                    name: "admin".to_string(),
+                    fee_bps: 0,
+                }],
+                fee_bps: 500,
+                fee_recipients: vec![],
+                accepted_denoms: vec![format!("cw20:{}", token)],
+                gateway: IPFS.to_string(),
+                push_payouts,
+                confirmation_window: 14 * 24 * 60 * 60,
+                ship_window: 7 * 24 * 60 * 60,
+                max_arbiter_fee_bps: 1_000,
+            },
+            &[],
+            "julian",
+            None,
+        )
+        .unwrap();
+
+    Suite {
+        app,
+        julian,
+        token,
+        admin,
+        seller,
+        buyer,
+    }
+}
+
+impl Suite {
+    fn create_listing(&mut self, price: u64) {
+        let msg = ExecuteMsg::CreateListing {
+            listing_title: "Vintage Camera".to_string(),
+            external_id: IPFS_LINK.to_string(),
+            text: "Selling my vintage camera in excellent condition".to_string(),
+            tags: vec!["Camera".to_string()],
+            contact: "Signal: +1234567890".to_string(),
+            price,
+            denom: format!("cw20:{}", self.token),
+            quantity: 1,
+            variants: vec![],
+            shipping_options: vec![],
+            ship_within: None,
+        };
+        self.app
+            .execute_contract(self.seller.clone(), self.julian.clone(), &msg, &[])
+            .unwrap();
+    }
+
+    fn send_tokens(&mut self, amount: u128, msg: &ReceiveMsg) -> AnyResult<()> {
+        let msg = Cw20ExecuteMsg::Send {
+            contract: self.julian.to_string(),
+            amount: Uint128::new(amount),
+            msg: to_json_binary(msg).unwrap(),
+        };
+        self.app
+            .execute_contract(self.buyer.clone(), self.token.clone(), &msg, &[])
+            .map(|_| ())
+    }
+
+    fn balance(&self, address: &Addr) -> u128 {
+        let res: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                &self.token,
+                &Cw20QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        res.balance.u128()
+    }
+}
+
+//Test buying with cw20 tokens through Receive and releasing them with the fee split
+#[test]
+fn test_cw20_purchase_and_release() {
+    let mut suite = setup(true);
+    suite.create_listing(100_000);
+
+    //underpaying is rejected and the tokens stay with the buyer
+    let err = suite
+        .send_tokens(
+            99_999,
+            &ReceiveMsg::Purchase {
+                listing_id: 1,
+                variant_id: None,
+                shipping_id: None,
+            },
+        )
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("Insufficient funds"));
+    assert_eq!(suite.balance(&suite.buyer.clone()), 1_000_000);
+
+    suite
+        .send_tokens(
+            100_000,
+            &ReceiveMsg::Purchase {
+                listing_id: 1,
+                variant_id: None,
+                shipping_id: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(suite.balance(&suite.julian.clone()), 100_000);
+
+    //the token balance is read from the cw20 contract and fully covered by escrow
+    let summary: EscrowSummaryResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(suite.julian.clone(), &QueryMsg::EscrowSummary {})
+        .unwrap();
+    assert_eq!(summary.denoms.len(), 1);
+    assert_eq!(summary.denoms[0].contract_balance, Uint128::new(100_000));
+    assert_eq!(summary.denoms[0].escrowed, Uint128::new(100_000));
+    assert!(summary.denoms[0].excess.is_zero());
+
+    let (seller, buyer, julian) = (
+        suite.seller.clone(),
+        suite.buyer.clone(),
+        suite.julian.clone(),
+    );
+    suite
+        .app
+        .execute_contract(
+            seller.clone(),
+            julian.clone(),
+            &ExecuteMsg::SignShipped { order_id: 1 },
+            &[],
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            buyer,
+            julian.clone(),
+            &ExecuteMsg::SignReceived { order_id: 1 },
+            &[],
+        )
+        .unwrap();
+
+    assert_eq!(suite.balance(&seller), 95_000);
+    assert_eq!(suite.balance(&suite.admin.clone()), 5_000);
+    assert_eq!(suite.balance(&julian), 0);
+}
+
+//Test that tokens sent beyond the price are transferred back to the buyer
+#[test]
+fn test_cw20_overpayment_refunded() {
+    let mut suite = setup(true);
+    suite.create_listing(100_000);
+
+    suite
+        .send_tokens(
+            100_500,
+            &ReceiveMsg::Purchase {
+                listing_id: 1,
+                variant_id: None,
+                shipping_id: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(suite.balance(&suite.buyer.clone()), 900_000);
+    assert_eq!(suite.balance(&suite.julian.clone()), 100_000);
+}
+
+//Test that refunds and arbitration pay cw20 listings back in tokens
+#[test]
+fn test_cw20_refund_and_arbitration() {
+    let mut suite = setup(true);
+    suite.create_listing(100_000);
+    let (seller, buyer, julian, admin) = (
+        suite.seller.clone(),
+        suite.buyer.clone(),
+        suite.julian.clone(),
+        suite.admin.clone(),
+    );
+
+    //buyer cancels before shipping and is refunded in tokens
+    suite
+        .send_tokens(
+            100_000,
+            &ReceiveMsg::Purchase {
+                listing_id: 1,
+                variant_id: None,
+                shipping_id: None,
+            },
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            buyer.clone(),
+            julian.clone(),
+            &ExecuteMsg::CancelPurchase { order_id: 1 },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.balance(&buyer), 1_000_000);
+
+    //the listing went back on sale, the buyer purchases it again as a new order,
+    //disputes, and the arbiter refunds them
+    suite
+        .send_tokens(
+            100_000,
+            &ReceiveMsg::Purchase {
+                listing_id: 1,
+                variant_id: None,
+                shipping_id: None,
+            },
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            seller,
+            julian.clone(),
+            &ExecuteMsg::SignShipped { order_id: 2 },
+            &[],
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            buyer.clone(),
+            julian.clone(),
+            &ExecuteMsg::RequestArbitration {
+                order_id: 2,
+                milestone_id: None,
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            admin,
+            julian.clone(),
+            &ExecuteMsg::Arbitrate {
+                order_id: 2,
+                ruling: RulingMsg {
+                    buyer_bps: 10_000,
+                    seller_bps: 0,
+                    arbiter_bps: 0,
+                },
+                milestone_id: None,
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.balance(&buyer), 1_000_000);
+    assert_eq!(suite.balance(&julian), 0);
+}
+
+//Test that pull mode credits cw20 payouts and Withdraw transfers them out
+#[test]
+fn test_cw20_pull_payout_withdraw() {
+    let mut suite = setup(false);
+    suite.create_listing(100_000);
+    let (seller, buyer, julian, admin) = (
+        suite.seller.clone(),
+        suite.buyer.clone(),
+        suite.julian.clone(),
+        suite.admin.clone(),
+    );
+    let denom = format!("cw20:{}", suite.token);
+
+    suite
+        .send_tokens(
+            100_000,
+            &ReceiveMsg::Purchase {
+                listing_id: 1,
+                variant_id: None,
+                shipping_id: None,
+            },
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            seller.clone(),
+            julian.clone(),
+            &ExecuteMsg::SignShipped { order_id: 1 },
+            &[],
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            buyer,
+            julian.clone(),
+            &ExecuteMsg::SignReceived { order_id: 1 },
+            &[],
+        )
+        .unwrap();
+
+    //funds stay in the contract until withdrawn
+    assert_eq!(suite.balance(&seller), 0);
+    assert_eq!(suite.balance(&julian), 100_000);
+
+    suite
+        .app
+        .execute_contract(
+            seller.clone(),
+            julian.clone(),
+            &ExecuteMsg::Withdraw {
+                denom: denom.clone(),
+                amount: None,
+            },
+            &[],
+        )
+        .unwrap();
+    suite
+        .app
+        .execute_contract(
+            admin.clone(),
+            julian.clone(),
+            &ExecuteMsg::Withdraw {
+                denom,
+                amount: Some(Uint128::new(5_000)),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.balance(&seller), 95_000);
+    assert_eq!(suite.balance(&admin), 5_000);
+    assert_eq!(suite.balance(&julian), 0);
+}
+
+//Test that tokens outside the allowlist cannot buy a listing
+#[test]
+fn test_cw20_wrong_token_rejected() {
+    let mut suite = setup(true);
+    suite.create_listing(100_000);
+
+    let buyer = suite.buyer.clone();
+    let other_token = instantiate_token(&mut suite.app, &buyer, "other", &[(&buyer, 1_000_000)]);
+    let msg = Cw20ExecuteMsg::Send {
+        contract: suite.julian.to_string(),
+        amount: Uint128::new(100_000),
+        msg: to_json_binary(&ReceiveMsg::Purchase {
+            listing_id: 1,
+            variant_id: None,
+            shipping_id: None,
+        })
+        .unwrap(),
+    };
+    let err = suite
+        .app
+        .execute_contract(suite.buyer.clone(), other_token, &msg, &[])
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("Denom not accepted"));
+}
+
+//Test bidding on a cw20 auction through Receive, with a raised bid refunding the earlier one
+#[test]
+fn test_cw20_auction_bid_and_settle() {
+    let mut suite = setup(true);
+    let (seller, buyer, julian) = (
+        suite.seller.clone(),
+        suite.buyer.clone(),
+        suite.julian.clone(),
+    );
+    let end_time = suite.app.block_info().time.plus_seconds(3600);
+    let msg = ExecuteMsg::CreateAuction {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Selling my vintage camera in excellent condition".to_string(),
+        tags: vec!["Camera".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        denom: format!("cw20:{}", suite.token),
+        start_price: 100_000,
+        reserve_price: 0,
+        min_increment: 10_000,
+        end_time,
+        extension_seconds: 300,
+    };
+    suite
+        .app
+        .execute_contract(seller, julian.clone(), &msg, &[])
+        .unwrap();
+
+    let bid = ReceiveMsg::PlaceBid { listing_id: 1 };
+    suite.send_tokens(100_000, &bid).unwrap();
+    suite.send_tokens(105_000, &bid).unwrap_err();
+    suite.send_tokens(150_000, &bid).unwrap();
+    assert_eq!(suite.balance(&buyer), 850_000);
+    assert_eq!(suite.balance(&julian), 150_000);
+
+    suite.app.update_block(|block| block.time = end_time);
+    suite
+        .app
+        .execute_contract(
+            buyer.clone(),
+            julian.clone(),
+            &ExecuteMsg::SettleAuction { listing_id: 1 },
+            &[],
+        )
+        .unwrap();
+    let res: OrderResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(julian, &QueryMsg::Order { order_id: 1 })
+        .unwrap();
+    let order = res.order.unwrap();
+    assert_eq!((order.buyer, order.price), (buyer, 150_000));
+}
+
+//Test that a cart paid in cw20 tokens is bought all or nothing
+#[test]
+fn test_cw20_purchase_many_is_atomic() {
+    let mut suite = setup(true);
+    suite.create_listing(100_000);
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Film Roll".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Expired film, stored cold".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 50_000,
+        denom: format!("cw20:{}", suite.token),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let (seller, buyer, julian) = (
+        suite.seller.clone(),
+        suite.buyer.clone(),
+        suite.julian.clone(),
+    );
+    suite
+        .app
+        .execute_contract(seller, julian.clone(), &msg, &[])
+        .unwrap();
+
+    //the second unit of listing 1 doesn't exist, so nothing in the cart is bought
+    let cart = |listing_ids| ReceiveMsg::PurchaseMany {
+        listing_ids,
+        group_by_seller: false,
+        region: None,
+    };
+    let err = suite
+        .send_tokens(250_000, &cart(vec![2, 1, 1]))
+        .unwrap_err();
+    assert!(err.root_cause().to_string().contains("sold out"));
+    assert_eq!(suite.balance(&buyer), 1_000_000);
+    let res: OrderResponse = suite
+        .app
+        .wrap()
+        .query_wasm_smart(julian.clone(), &QueryMsg::Order { order_id: 1 })
+        .unwrap();
+    assert!(res.order.is_none());
+
+    suite.send_tokens(150_000, &cart(vec![2, 1])).unwrap();
+    assert_eq!(suite.balance(&buyer), 850_000);
+    assert_eq!(suite.balance(&julian), 150_000);
+}
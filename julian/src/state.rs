@@ -1,12 +1,52 @@
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
-use cosmwasm_std::Addr;
+use std::fmt;
+
+use cosmwasm_std::{Addr, Binary, Timestamp, Uint128};
 use cw_storage_plus::{Item, Map};
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Config {
     pub admin: Addr,
+    //marketplace fee in basis points of the sale price
+    pub fee_bps: u64,
+    //the fee is split between these by weight when a sale completes, when empty the whole fee goes
+    //to whoever is admin at the time
+    pub fee_recipients: Vec<FeeRecipient>,
+    //native denoms sellers may price listings in
+    pub accepted_denoms: Vec<String>,
+    //ipfs gateway prefix every listing external_id must start with
+    pub gateway: String,
+    pub max_text_length: u64,
+    pub max_id_length: u64,
+    //send payouts straight to recipients instead of crediting BALANCES for them to withdraw
+    pub push_payouts: bool,
+    //seconds a buyer has after shipment to sign received or dispute before anyone can release
+    //the funds to the seller
+    pub confirmation_window: u64,
+    //seconds a seller has from purchase to ship before anyone can refund the buyer, listings can
+    //set their own
+    pub ship_window: u64,
+    //the most any ruling can award an arbiter, whatever that arbiter's own fee
+    pub max_arbiter_fee_bps: u64,
+}
+//allowed to sign shipped on behalf of sellers and settle disputes while active
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Arbiter {
+    pub address: Addr,
+    pub name: String,
+    //the most a ruling can award the arbiter, in basis points of the disputed amount
+    pub fee_bps: u64,
+    //removed arbiters are kept inactive so past rulings still show who made them
+    pub active: bool,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct FeeRecipient {
+    //treasury, arbiter pool, community fund...
+    pub name: String,
+    pub address: Addr,
+    pub weight: u64,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Profile {
@@ -19,6 +59,8 @@ pub struct Profile {
     pub rating_count: u64,
     //rating_count divided by ratings
     pub average_rating: u64,
+    //orders refunded because the seller didn't ship in time
+    pub failed_shipments: u64,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Listing {
@@ -28,28 +70,511 @@ pub struct Listing {
     pub listing_title: String,
     //ipfs link (for photograph)
     pub external_id: String,
-    //price of item
+    //price of item, the lowest variant price when the listing has variants
     pub price: u64,
+    //denom the price is paid in, chosen from the accepted denoms
+    pub denom: String,
     //store summary of listing / edits
     pub text: String,
     pub tags: Vec<String>,
     pub seller: String,
     //Signal or Session contact
     pub contact: String,
-    //If true, item is unbuyable
-    pub bought: bool,
-    //stores buyer address to ensure signer is legit buyer
-    pub buyer: Option<String>,
-    //seller marks shipped which is one key to releasing funds
-    pub shipped: bool,
-    //buyer marks received which is one key to releasing funds
-    pub received: bool,
-    //arbitration request
-    pub arbitration_requested: bool,
+    //only Active listings can be bought
+    pub status: ListingStatus,
+    //units still for sale, summed over the variants when there are any
+    pub quantity: u64,
+    //units held by orders in escrow, they return to quantity if the order is refunded
+    pub reserved: u64,
+    //sizes, colors and the like, each bought on its own through Purchase's variant_id
+    pub variants: Vec<Variant>,
+    //ways the seller ships, one is picked at purchase and paid on top of the price
+    pub shipping_options: Vec<ShippingOption>,
+    //overrides the configured ship window for this listing's orders
+    pub ship_within: Option<u64>,
+    //bought outright through Purchase, or sold to the highest bidder
+    pub sale_type: SaleType,
     pub creation_date: String,
     pub last_edit_date: Option<String>,
 }
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Variant {
+    //position in the listing's variant list, starting at 1
+    pub variant_id: u64,
+    pub name: String,
+    pub price: u64,
+    pub quantity: u64,
+    pub reserved: u64,
+    //optional image, held to the same gateway rules as the listing's external_id
+    pub external_id: Option<String>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct ShippingOption {
+    //position in the listing's shipping options, starting at 1
+    pub shipping_id: u64,
+    pub name: String,
+    //paid to the seller in full, the marketplace fee is only taken on the item
+    pub price: u64,
+    //region tags the option ships to, matched against PurchaseMany's region
+    pub regions: Vec<String>,
+    pub estimated_days: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SaleType {
+    FixedPrice,
+    EnglishAuction(EnglishAuction),
+    DutchAuction(DutchAuction),
+    SealedBidAuction(SealedBidAuction),
+    Service(Service),
+}
+
+impl SaleType {
+    //sold to the highest bidder rather than bought through Purchase
+    pub fn takes_bids(&self) -> bool {
+        matches!(
+            self,
+            SaleType::EnglishAuction(_) | SaleType::SealedBidAuction(_)
+        )
+    }
+}
+
+impl fmt::Display for SaleType {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let sale_type = match self {
+            SaleType::FixedPrice => "fixed_price",
+            SaleType::EnglishAuction(_) => "english_auction",
+            SaleType::DutchAuction(_) => "dutch_auction",
+            SaleType::SealedBidAuction(_) => "sealed_bid_auction",
+            SaleType::Service(_) => "service",
+        };
+        write!(f, "{}", sale_type)
+    }
+}
+//ascending price auction for a single unit, the listing price is the start price
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct EnglishAuction {
+    pub start_price: u64,
+    //the highest bid must reach this for SettleAuction to sell, 0 for no reserve
+    pub reserve_price: u64,
+    //each bid must beat the highest one by at least this much
+    pub min_increment: u64,
+    pub end_time: Timestamp,
+    //a bid placed this close to end_time pushes it out to this long after the bid
+    pub extension_seconds: u64,
+    //held in escrow until outbid or settled
+    pub highest_bid: Option<Bid>,
+    pub bid_count: u64,
+}
+//descending price sale, bought through Purchase at whatever the price is when it executes
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct DutchAuction {
+    pub start_price: u64,
+    //the price stays here from end_time on
+    pub floor_price: u64,
+    pub start_time: Timestamp,
+    pub end_time: Timestamp,
+    //the price drops every step_seconds instead of every second, 0 for a linear drop
+    pub step_seconds: u64,
+}
+
+impl DutchAuction {
+    //rounds up, a partial drop stays with the seller
+    pub fn price_at(&self, now: Timestamp) -> u64 {
+        if now >= self.end_time {
+            return self.floor_price;
+        }
+        let duration = self.end_time.seconds() - self.start_time.seconds();
+        let mut elapsed = now.seconds().saturating_sub(self.start_time.seconds());
+        if self.step_seconds > 0 {
+            elapsed -= elapsed % self.step_seconds;
+        }
+        let drop =
+            (self.start_price - self.floor_price) as u128 * elapsed as u128 / duration as u128;
+        self.start_price - drop as u64
+    }
+}
+//single unit auction where bidders commit to a hidden bid, then reveal it once commitments close
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SealedBidAuction {
+    //smallest deposit a commitment can carry, the deposit caps the bid it can reveal
+    pub min_deposit: u64,
+    //reveals below this are not valid bids, the listing price shows it
+    pub reserve_price: u64,
+    //commitments are taken until commit_end, reveals from then until reveal_end
+    pub commit_end: Timestamp,
+    pub reveal_end: Timestamp,
+    //share of an unrevealed deposit paid to the seller when the bidder reclaims it
+    pub forfeit_bps: u64,
+    //highest valid reveal so far
+    pub highest_bid: Option<Bid>,
+    pub commitment_count: u64,
+    //valid reveals, numbered in BIDS like english auction bids
+    pub bid_count: u64,
+    //deposits not yet reclaimed or settled, the listing can't be deleted while any are left
+    pub deposits_held: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct SealedBid {
+    pub bidder: Addr,
+    //sha256 of "<listing_id>:<bidder>:<amount>:<salt>"
+    pub commitment: Binary,
+    pub deposit: u64,
+    pub committed_at: Timestamp,
+    //set once revealed
+    pub amount: Option<u64>,
+}
+//work bought through Purchase and paid out milestone by milestone, the listing price is their sum
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Service {
+    pub milestones: Vec<MilestoneTerms>,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct MilestoneTerms {
+    pub description: String,
+    pub amount: u64,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Bid {
+    //position in the auction's bid history, starting at 1
+    pub bid_id: u64,
+    pub bidder: Addr,
+    pub amount: u64,
+    pub placed_at: Timestamp,
+}
+//an arbiter's split of a disputed order or milestone, with what each side was paid
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Ruling {
+    pub order_id: u64,
+    pub milestone_id: Option<u64>,
+    pub arbiter: Addr,
+    pub buyer_bps: u64,
+    pub seller_bps: u64,
+    pub arbiter_bps: u64,
+    pub buyer_amount: u64,
+    pub seller_amount: u64,
+    pub arbiter_amount: u64,
+    pub ruled_at: Timestamp,
+}
+//a split of an order's escrow offered by one side, settled as soon as the other side accepts
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Settlement {
+    pub order_id: u64,
+    pub milestone_id: Option<u64>,
+    pub proposer: Addr,
+    //the seller gets the rest, less the marketplace fee
+    pub buyer_share_bps: u64,
+    pub proposed_at: Timestamp,
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ListingStatus {
+    //for sale, at least one unit in stock
+    Active,
+    //every unit is sold or reserved, back to Active on a restock or refund
+    SoldOut,
+    //taken down by the seller
+    Cancelled,
+    //auction settled without a winning bid
+    Ended,
+}
+
+impl ListingStatus {
+    pub fn can_transition_to(self, to: ListingStatus) -> bool {
+        use ListingStatus::*;
+        matches!(
+            (self, to),
+            (Active, SoldOut)
+                | (Active, Cancelled)
+                | (Active, Ended)
+                | (SoldOut, Active)
+                | (SoldOut, Cancelled)
+                | (Ended, Cancelled)
+        )
+    }
+}
+
+impl fmt::Display for ListingStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            ListingStatus::Active => "active",
+            ListingStatus::SoldOut => "sold_out",
+            ListingStatus::Cancelled => "cancelled",
+            ListingStatus::Ended => "ended",
+        };
+        write!(f, "{}", status)
+    }
+}
+//a purchase of a listing or a fulfilled wanted ad, kept after settlement as the record of the sale
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Order {
+    pub order_id: u64,
+    //exactly one of listing_id and wanted_ad_id is set
+    pub listing_id: Option<u64>,
+    pub wanted_ad_id: Option<u64>,
+    pub buyer: Addr,
+    pub seller: Addr,
+    pub variant_id: Option<u64>,
+    //price and denom at the time of purchase, later listing edits don't touch the order
+    pub price: u64,
+    pub denom: String,
+    //the shipping option picked, escrowed on top of the price
+    pub shipping: Option<ShippingOption>,
+    pub status: OrderStatus,
+    //when the order entered each state
+    pub purchased_at: Timestamp,
+    //unshipped orders can be refunded by anyone after this
+    pub ship_by: Timestamp,
+    pub shipped_at: Option<Timestamp>,
+    pub disputed_at: Option<Timestamp>,
+    pub completed_at: Option<Timestamp>,
+    pub refunded_at: Option<Timestamp>,
+    //set when shipped, after this anyone can release an undisputed order to the seller
+    pub confirm_by: Option<Timestamp>,
+    //orders from one seller bought together through PurchaseMany can share a shipment, named after
+    //its first order
+    pub shipment_id: Option<u64>,
+    //service orders release their escrow one milestone at a time, empty for everything else
+    pub milestones: Vec<Milestone>,
+}
+
+impl Order {
+    pub fn shipping_price(&self) -> u64 {
+        self.shipping.as_ref().map_or(0, |shipping| shipping.price)
+    }
+
+    //what the buyer paid
+    pub fn total(&self) -> u64 {
+        self.price + self.shipping_price()
+    }
+
+    //what the contract still holds for the order, less the milestones already settled
+    pub fn escrowed(&self) -> u64 {
+        if self.milestones.is_empty() {
+            return self.total();
+        }
+        self.milestones
+            .iter()
+            .filter(|milestone| !milestone.status.is_settled())
+            .map(|milestone| milestone.amount)
+            .sum()
+    }
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Milestone {
+    //position in the service's milestones, starting at 1, delivered in that order
+    pub milestone_id: u64,
+    pub description: String,
+    pub amount: u64,
+    pub status: MilestoneStatus,
+    pub delivered_at: Option<Timestamp>,
+    pub settled_at: Option<Timestamp>,
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum MilestoneStatus {
+    //not delivered yet
+    Pending,
+    //waiting on the buyer's approval
+    Delivered,
+    //waiting on an arbiter
+    Disputed,
+    //paid out to the seller
+    Released,
+    //returned to the buyer
+    Refunded,
+}
+
+impl MilestoneStatus {
+    pub fn can_transition_to(self, to: MilestoneStatus) -> bool {
+        use MilestoneStatus::*;
+        matches!(
+            (self, to),
+            (Pending, Delivered)
+                | (Pending, Disputed)
+                | (Pending, Refunded)
+                | (Delivered, Disputed)
+                | (Delivered, Released)
+                | (Delivered, Refunded)
+                | (Disputed, Released)
+                | (Disputed, Refunded)
+        )
+    }
+
+    pub fn is_settled(self) -> bool {
+        matches!(self, MilestoneStatus::Released | MilestoneStatus::Refunded)
+    }
+}
+
+impl fmt::Display for MilestoneStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            MilestoneStatus::Pending => "pending",
+            MilestoneStatus::Delivered => "delivered",
+            MilestoneStatus::Disputed => "disputed",
+            MilestoneStatus::Released => "released",
+            MilestoneStatus::Refunded => "refunded",
+        };
+        write!(f, "{}", status)
+    }
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderStatus {
+    //paid for, funds held in escrow
+    Purchased,
+    //seller (or an arbiter) signed shipped
+    Shipped,
+    //buyer or seller asked an arbiter to settle the sale
+    Disputed,
+    //funds released to the seller
+    Completed,
+    //funds returned to the buyer
+    Refunded,
+}
+
+impl OrderStatus {
+    //the only lifecycle moves an order can make
+    pub fn can_transition_to(self, to: OrderStatus) -> bool {
+        use OrderStatus::*;
+        matches!(
+            (self, to),
+            (Purchased, Shipped)
+                | (Purchased, Refunded)
+                | (Shipped, Disputed)
+                | (Shipped, Completed)
+                | (Shipped, Refunded)
+                | (Disputed, Completed)
+                | (Disputed, Refunded)
+        )
+    }
+}
+
+impl fmt::Display for OrderStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            OrderStatus::Purchased => "purchased",
+            OrderStatus::Shipped => "shipped",
+            OrderStatus::Disputed => "disputed",
+            OrderStatus::Completed => "completed",
+            OrderStatus::Refunded => "refunded",
+        };
+        write!(f, "{}", status)
+    }
+}
+//a buyer's escrowed offer below the list price, accepted it becomes an order
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Offer {
+    pub offer_id: u64,
+    pub listing_id: u64,
+    pub buyer: Addr,
+    pub variant_id: Option<u64>,
+    //what the buyer escrowed for the item
+    pub price: u64,
+    //the seller's answer, the buyer tops up the difference to accept it
+    pub counter_price: Option<u64>,
+    pub denom: String,
+    //escrowed on top of the price and carried over to the order
+    pub shipping: Option<ShippingOption>,
+    pub status: OfferStatus,
+    pub created_at: Timestamp,
+    //can't be accepted from then on, and anyone can return the funds to the buyer
+    pub expires_at: Timestamp,
+    //set once accepted
+    pub order_id: Option<u64>,
+}
+
+impl Offer {
+    //the offer price plus shipping
+    pub fn escrowed(&self) -> u64 {
+        self.price + self.shipping.as_ref().map_or(0, |shipping| shipping.price)
+    }
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum OfferStatus {
+    //waiting on the seller
+    Open,
+    //waiting on the buyer
+    Countered,
+    Accepted,
+    //turned down by the seller, or refunded because another offer took the last unit
+    Rejected,
+    Withdrawn,
+    Expired,
+}
+
+impl OfferStatus {
+    pub fn is_open(self) -> bool {
+        matches!(self, OfferStatus::Open | OfferStatus::Countered)
+    }
+}
+
+impl fmt::Display for OfferStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            OfferStatus::Open => "open",
+            OfferStatus::Countered => "countered",
+            OfferStatus::Accepted => "accepted",
+            OfferStatus::Rejected => "rejected",
+            OfferStatus::Withdrawn => "withdrawn",
+            OfferStatus::Expired => "expired",
+        };
+        write!(f, "{}", status)
+    }
+}
+//a buyer's request for an item, with the budget escrowed up front
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct WantedAd {
+    pub wanted_ad_id: u64,
+    pub requester: Addr,
+    pub title: String,
+    pub text: String,
+    pub tags: Vec<String>,
+    pub budget: u64,
+    pub denom: String,
+    pub status: WantedAdStatus,
+    pub created_at: Timestamp,
+    pub proposal_count: u64,
+    //set once the requester accepts a proposal
+    pub order_id: Option<u64>,
+}
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum WantedAdStatus {
+    //taking proposals
+    Open,
+    //a proposal was accepted and became an order
+    Filled,
+    //taken down by the requester, budget refunded
+    Cancelled,
+}
+
+impl fmt::Display for WantedAdStatus {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let status = match self {
+            WantedAdStatus::Open => "open",
+            WantedAdStatus::Filled => "filled",
+            WantedAdStatus::Cancelled => "cancelled",
+        };
+        write!(f, "{}", status)
+    }
+}
+//a seller's offer to fulfil a wanted ad
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
+pub struct Proposal {
+    //position in the wanted ad's proposals, starting at 1
+    pub proposal_id: u64,
+    pub seller: Addr,
+    //at most the budget, the rest goes back to the requester on acceptance
+    pub price: u64,
+    pub text: String,
+    //optional photo, held to the same gateway rules as listings
+    pub external_id: Option<String>,
+    pub contact: String,
+    pub submitted_at: Timestamp,
+}
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, Eq, JsonSchema)]
 pub struct Relationship {
     pub seller: String,
     pub buyer: String,
@@ -57,6 +582,9 @@ pub struct Relationship {
 }
 
 pub const CONFIG: Item<Config> = Item::new("config");
+//admin proposed by the current admin, becomes admin once it accepts
+pub const PENDING_ADMIN: Item<Addr> = Item::new("pending_admin");
+pub const ARBITERS: Map<Addr, Arbiter> = Map::new("arbiters");
 pub const PROFILES: Map<Addr, Profile> = Map::new("profiles");
 pub const PROFILE_NAME: Map<Addr, String> = Map::new("profile_name");
 pub const LISTING: Map<u64, Listing> = Map::new("listing");
@@ -64,3 +592,35 @@ pub const LAST_LISTING_ID: Item<u64> = Item::new("last_listing_id");
 pub const LISTING_COUNT: Item<u64> = Item::new("number_of_listings");
 pub const LISTING_TITLES: Map<String, u64> = Map::new("listing_titles");
 pub const RELATIONSHIPS: Map<String, Relationship> = Map::new("relationship");
+//(listing_id, bid_id), every bid placed on an auction
+pub const BIDS: Map<(u64, u64), Bid> = Map::new("bids");
+//(listing_id, bidder), one commitment per bidder on a sealed bid auction
+pub const SEALED_BIDS: Map<(u64, Addr), SealedBid> = Map::new("sealed_bids");
+pub const ORDERS: Map<u64, Order> = Map::new("orders");
+pub const LAST_ORDER_ID: Item<u64> = Item::new("last_order_id");
+//(buyer or seller, order_id), lets either side page through their orders
+pub const ORDERS_BY_ADDRESS: Map<(Addr, u64), ()> = Map::new("orders_by_address");
+//(order_id, milestone_id), rulings on a whole order are under milestone 0
+pub const RULINGS: Map<(u64, u64), Ruling> = Map::new("rulings");
+//(order_id, milestone_id) like RULINGS, one open proposal each, cleared once accepted or revoked
+pub const SETTLEMENTS: Map<(u64, u64), Settlement> = Map::new("settlements");
+//(shipment_id, order_id), the orders signed shipped together
+pub const SHIPMENTS: Map<(u64, u64), ()> = Map::new("shipments");
+pub const OFFERS: Map<u64, Offer> = Map::new("offers");
+pub const LAST_OFFER_ID: Item<u64> = Item::new("last_offer_id");
+//(listing_id, offer_id) of offers still open or countered, cleared as they close
+pub const OPEN_OFFERS: Map<(u64, u64), ()> = Map::new("open_offers");
+pub const WANTED_ADS: Map<u64, WantedAd> = Map::new("wanted_ads");
+pub const LAST_WANTED_AD_ID: Item<u64> = Item::new("last_wanted_ad_id");
+//(wanted_ad_id, proposal_id)
+pub const PROPOSALS: Map<(u64, u64), Proposal> = Map::new("proposals");
+//(tag, wanted_ad_id) of open wanted ads, cleared once filled or cancelled
+pub const WANTED_ADS_BY_TAG: Map<(String, u64), ()> = Map::new("wanted_ads_by_tag");
+//(requester, wanted_ad_id) of every wanted ad the address posted
+pub const WANTED_ADS_BY_REQUESTER: Map<(Addr, u64), ()> = Map::new("wanted_ads_by_requester");
+//payouts owed to (address, denom), withdrawn through Withdraw
+pub const BALANCES: Map<(Addr, String), Uint128> = Map::new("balances");
+//per denom totals of buyer funds held for open purchases and of unwithdrawn BALANCES,
+//anything the contract holds beyond the two is stray and can be swept by the admin
+pub const ESCROWED: Map<String, Uint128> = Map::new("escrowed");
+pub const OWED: Map<String, Uint128> = Map::new("owed");
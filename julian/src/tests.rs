@@ -1,36 +1,3227 @@
 //cargo tarpaulin --ignore-tests = 79.23% coverage, 290/366 lines covered
-//2 tests fail due to the new way that cosmwasm deals with bech32 addresses (cosmwasmaddr1...). Uncomment test address and comment out legit addresses in contract.rs for testing.
-use crate::contract::{execute, instantiate, migrate, query};
+use crate::contract::{execute, instantiate, migrate, query, sealed_bid_commitment};
 use crate::msg::{
-    AllListingsResponse, ArbitrationListingsResponse, ExecuteMsg, InstantiateMsg,
-    ListingCountResponse, ListingResponse, MigrateMsg, QueryMsg, SearchListingsResponse,
-    ProfileResponse,
+    AllListingsResponse, ArbiterMsg, ArbiterResponse, ArbitersResponse,
+    ArbitrationListingsResponse, BalanceResponse, BidsResponse, ConfigResponse,
+    CurrentPriceResponse, DenomEscrowSummary, EscrowSummaryResponse, ExecuteMsg,
+    FeePreviewResponse, FeeRecipientMsg, InstantiateMsg, ListingCountResponse, ListingResponse,
+    MigrateMsg, MilestoneMsg, OfferResponse, OffersResponse, OrderResponse, OrdersResponse,
+    ProfileResponse, ProposalsResponse, QueryMsg, RulingMsg, RulingsResponse, SealedBidResponse,
+    SearchListingsResponse, SettlementsResponse, ShippingOptionMsg, VariantMsg, WantedAdsResponse,
 };
+use crate::state::{
+    ListingStatus, MilestoneStatus, OfferStatus, OrderStatus, WantedAdStatus, ORDERS,
+};
+use crate::ContractError;
 use cosmwasm_std::testing::{message_info, mock_dependencies, mock_env};
-use cosmwasm_std::{attr, coin, from_json, Response};
+use cosmwasm_std::{attr, coin, from_json, Addr, BankMsg, CosmosMsg, Env, Response, Uint128};
+
+const JUNO: &str = "ujuno";
+const USDC: &str = "ibc/usdc";
+const IPFS: &str = "https://gateway.pinata.cloud/ipfs/";
+const IPFS_LINK: &str =
+    "https://gateway.pinata.cloud/ipfs/QmQSXMeJRyodyVESWVXT8gd7kQhjrV7sguLnsrXSd6YzvT";
+
+//instantiator doubles as the arbiter so arbitration can be exercised in tests
+fn default_instantiate_msg(instantiator: &Addr) -> InstantiateMsg {
+    InstantiateMsg {
+        admin: None,
+        arbiters: vec![ArbiterMsg {
+            address: instantiator.to_string(),
+            name: "instantiator".to_string(),
+            fee_bps: 1_000,
+        }],
+        fee_bps: 500,
+        fee_recipients: vec![],
+        accepted_denoms: vec![JUNO.to_string()],
+        gateway: IPFS.to_string(),
+        push_payouts: false,
+        confirmation_window: 14 * 24 * 60 * 60,
+        ship_window: 7 * 24 * 60 * 60,
+        max_arbiter_fee_bps: 2_000,
+    }
+}
+
+//Test that the contract is instantiated correctly
+#[test]
+fn test_instantiate() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "instantiate"),
+            attr("admin", instantiator.to_string())
+        ]
+    );
+}
+
+//Test that the instantiate-time configuration is stored and queryable
+#[test]
+fn test_query_config() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let admin = deps.api.addr_make("admin");
+    let info = message_info(&instantiator, &[]);
+
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.admin = Some(admin.to_string());
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = QueryMsg::Config {};
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: ConfigResponse = from_json(&bin).unwrap();
+    assert_eq!(res.config.admin, admin);
+    assert_eq!(res.config.fee_bps, 500);
+    assert!(res.config.fee_recipients.is_empty());
+    assert_eq!(res.config.accepted_denoms, vec![JUNO.to_string()]);
+    assert_eq!(res.config.gateway, IPFS);
+}
+
+//Test that a fee above 100 percent or a zero weight recipient is rejected at instantiate
+#[test]
+fn test_instantiate_invalid_fee() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.fee_bps = 10_001;
+    let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Fee must be at most 10000 bps and every fee recipient needs a non-zero weight"
+    );
+
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.fee_recipients = vec![FeeRecipientMsg {
+        name: "treasury".to_string(),
+        address: instantiator.to_string(),
+        weight: 0,
+    }];
+    let _err = instantiate(deps.as_mut(), env, info, msg).unwrap_err();
+}
+
+//Test that only the admin can update config and that old and new values are emitted
+#[test]
+fn test_update_config() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let treasury = deps.api.addr_make("treasury");
+    let random_address = deps.api.addr_make("random_address");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let msg = ExecuteMsg::UpdateConfig {
+        fee_bps: Some(300),
+        fee_recipients: Some(vec![FeeRecipientMsg {
+            name: "treasury".to_string(),
+            address: treasury.to_string(),
+            weight: 1,
+        }]),
+        accepted_denoms: None,
+        gateway: None,
+        max_text_length: Some(1000),
+        max_id_length: None,
+        push_payouts: None,
+        confirmation_window: None,
+        ship_window: None,
+        max_arbiter_fee_bps: None,
+    };
+
+    //non-admin cannot update config
+    let info = message_info(&random_address, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    let info = message_info(&instantiator, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "update_config"),
+            attr("old_fee_bps", "500"),
+            attr("new_fee_bps", "300"),
+            attr("old_fee_recipients", "admin"),
+            attr("new_fee_recipients", format!("treasury:{}:1", treasury)),
+            attr("old_max_text_length", "499"),
+            attr("new_max_text_length", "1000"),
+        ]
+    );
+
+    let msg = QueryMsg::Config {};
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: ConfigResponse = from_json(&bin).unwrap();
+    assert_eq!(res.config.fee_bps, 300);
+    assert_eq!(res.config.fee_recipients[0].address, treasury);
+    assert_eq!(res.config.max_text_length, 1000);
+}
+
+//Test the two step admin transfer
+#[test]
+fn test_admin_transfer() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let new_admin = deps.api.addr_make("new_admin");
+    let random_address = deps.api.addr_make("random_address");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //nothing to accept yet
+    let info = message_info(&new_admin, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::AcceptAdmin {}).unwrap_err();
+    assert_eq!(err.to_string(), "No admin transfer has been proposed");
+
+    let info = message_info(&instantiator, &[]);
+    let msg = ExecuteMsg::ProposeNewAdmin {
+        new_admin: new_admin.to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //admin does not change until the proposed address accepts
+    let msg = QueryMsg::Config {};
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ConfigResponse = from_json(&bin).unwrap();
+    assert_eq!(res.config.admin, instantiator);
+    assert_eq!(res.pending_admin, Some(new_admin.clone()));
+
+    //only the proposed address can accept
+    let info = message_info(&random_address, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::AcceptAdmin {}).unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    let info = message_info(&new_admin, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, ExecuteMsg::AcceptAdmin {}).unwrap();
+    assert_eq!(
+        res.attributes,
+        vec![
+            attr("action", "accept_admin"),
+            attr("old_admin", instantiator.to_string()),
+            attr("new_admin", new_admin.to_string()),
+        ]
+    );
+
+    let msg = QueryMsg::Config {};
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ConfigResponse = from_json(&bin).unwrap();
+    assert_eq!(res.config.admin, new_admin);
+    assert_eq!(res.pending_admin, None);
+
+    //the old admin has lost its rights
+    let info = message_info(&instantiator, &[]);
+    let msg = ExecuteMsg::ProposeNewAdmin {
+        new_admin: random_address.to_string(),
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    //without fee recipients the fee follows the admin
+    let msg = QueryMsg::FeePreview { price: 1_000_000 };
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: FeePreviewResponse = from_json(&bin).unwrap();
+    assert_eq!(res.split.shares[0].address, new_admin);
+    assert_eq!(res.split.shares[0].amount, Uint128::new(50_000));
+}
+
+//Test that listings can only be priced in accepted denoms and settle in the listing denom
+#[test]
+fn test_multi_denom_listing() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.accepted_denoms = vec![JUNO.to_string(), USDC.to_string()];
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let listing_buyer = deps.api.addr_make("listing_buyer");
+
+    //denoms outside the allowlist are rejected
+    let info = message_info(&listing_creator, &[]);
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Selling my vintage camera in excellent condition".to_string(),
+        tags: vec!["Camera".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        price: 100_000_000,
+        denom: "uosmo".to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Denom not accepted: uosmo");
+
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Selling my vintage camera in excellent condition".to_string(),
+        tags: vec!["Camera".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        price: 100_000_000,
+        denom: USDC.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //paying in a different accepted denom is not enough
+    let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let _err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+
+    let info = message_info(&listing_buyer, &[coin(100_000_000, USDC)]);
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //refund is credited back in the listing denom
+    let info = message_info(&listing_buyer, &[]);
+    let msg = ExecuteMsg::CancelPurchase { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = QueryMsg::Balance {
+        address: listing_buyer.to_string(),
+    };
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(100_000_000, USDC)]);
+}
+
+//Test that settlement credits the ledger by default and that credited funds can be withdrawn
+#[test]
+fn test_pull_payouts_and_withdraw() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let listing_buyer = deps.api.addr_make("listing_buyer");
+
+    let info = message_info(&listing_creator, &[]);
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Selling my vintage camera in excellent condition".to_string(),
+        tags: vec!["Camera".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        price: 100_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let info = message_info(&listing_creator, &[]);
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //nothing is pushed on release
+    let info = message_info(&listing_buyer, &[]);
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.messages.is_empty());
+
+    let msg = QueryMsg::Balance {
+        address: listing_creator.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(95_000_000, JUNO)]);
+
+    let msg = QueryMsg::Balance {
+        address: instantiator.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(5_000_000, JUNO)]);
+
+    //cannot withdraw more than was credited
+    let info = message_info(&listing_creator, &[]);
+    let msg = ExecuteMsg::Withdraw {
+        denom: JUNO.to_string(),
+        amount: Some(Uint128::new(95_000_001)),
+    };
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Insufficient balance. Requested: 95000001ujuno Available: 95000000ujuno"
+    );
+
+    //partial withdraw then the rest
+    let msg = ExecuteMsg::Withdraw {
+        denom: JUNO.to_string(),
+        amount: Some(Uint128::new(45_000_000)),
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: listing_creator.to_string(),
+            amount: vec![coin(45_000_000, JUNO)],
+        })
+    );
+    let msg = ExecuteMsg::Withdraw {
+        denom: JUNO.to_string(),
+        amount: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    assert!(res.attributes.contains(&attr("amount", "50000000")));
+    assert!(res.attributes.contains(&attr("remaining", "0")));
+
+    let msg = QueryMsg::Balance {
+        address: listing_creator.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert!(res.balances.is_empty());
+
+    let msg = ExecuteMsg::Withdraw {
+        denom: JUNO.to_string(),
+        amount: None,
+    };
+    let _err = execute(deps.as_mut(), env, info, msg).unwrap_err();
+}
+
+//Test that handlers reject moves the listing lifecycle does not allow
+#[test]
+fn test_listing_status_transitions() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let listing_buyer = deps.api.addr_make("listing_buyer");
+    let seller_info = message_info(&listing_creator, &[]);
+    let buyer_info = message_info(&listing_buyer, &[]);
+
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Selling my vintage camera in excellent condition".to_string(),
+        tags: vec!["Camera".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        price: 100_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+    //nothing to ship before anyone has paid
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let _err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+
+    let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+
+    //a paid for listing can't be bought twice, deleted or released before shipping
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Listing 1 is sold out");
+    let msg = ExecuteMsg::DeleteListing { listing_id: 1 };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Listing 1 has 1 units reserved by orders in escrow"
+    );
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
+    let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Order 1 cannot go from purchased to completed"
+    );
+    let msg = ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Order 1 cannot go from purchased to disputed"
+    );
+
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    assert!(res.attributes.contains(&attr("status", "shipped")));
+
+    //the buyer can no longer cancel on their own once the item is on its way
+    let msg = ExecuteMsg::CancelPurchase { order_id: 1 };
+    let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Order 1 cannot go from shipped to refunded"
+    );
+
+    //arbiters only step in once a dispute is raised
+    let msg = ExecuteMsg::Arbitrate {
+        order_id: 1,
+        ruling: RulingMsg {
+            buyer_bps: 10_000,
+            seller_bps: 0,
+            arbiter_bps: 0,
+        },
+        milestone_id: None,
+    };
+    let arbiter_info = message_info(&instantiator, &[]);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        arbiter_info.clone(),
+        msg.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Order 1 cannot go from shipped to refunded"
+    );
+    let dispute = ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), buyer_info, dispute).unwrap();
+    assert!(res.attributes.contains(&attr("status", "disputed")));
+    let res = execute(deps.as_mut(), env.clone(), arbiter_info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("status", "refunded")));
+}
+
+//Test that every purchase leaves an order behind and both sides can page through them
+#[test]
+fn test_order_history() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let listing_buyer = deps.api.addr_make("listing_buyer");
+    let seller_info = message_info(&listing_creator, &[]);
+    let buyer_info = message_info(&listing_buyer, &[]);
+    let paying_info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
+
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Selling my vintage camera in excellent condition".to_string(),
+        tags: vec!["Camera".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        price: 100_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+    //first order is cancelled and the listing goes back on sale
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), paying_info.clone(), msg.clone()).unwrap();
+    let cancel = ExecuteMsg::CancelPurchase { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), buyer_info.clone(), cancel).unwrap();
+
+    //second order runs to completion
+    let purchased_at = env.block.time;
+    let res = execute(deps.as_mut(), env.clone(), paying_info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("order_id", "2")));
+    env.block.time = env.block.time.plus_seconds(60);
+    let msg = ExecuteMsg::SignShipped { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+    env.block.time = env.block.time.plus_seconds(60);
+    let msg = ExecuteMsg::SignReceived { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), buyer_info, msg).unwrap();
+
+    let msg = QueryMsg::Order { order_id: 2 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    let order = res.order.unwrap();
+    assert_eq!(order.status, OrderStatus::Completed);
+    assert_eq!(order.purchased_at, purchased_at);
+    assert_eq!(order.shipped_at, Some(purchased_at.plus_seconds(60)));
+    assert_eq!(order.completed_at, Some(purchased_at.plus_seconds(120)));
+    assert_eq!(order.refunded_at, None);
+
+    //the listing survives the sale, closed
+    let msg = QueryMsg::Listing { listing_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ListingResponse = from_json(&bin).unwrap();
+    assert_eq!(res.listing.unwrap().status, ListingStatus::SoldOut);
+
+    for address in [&listing_buyer, &listing_creator] {
+        let msg = QueryMsg::OrdersByAddress {
+            address: address.to_string(),
+            limit: None,
+            start_after: None,
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: OrdersResponse = from_json(&bin).unwrap();
+        let statuses: Vec<_> = res.orders.iter().map(|o| (o.order_id, o.status)).collect();
+        assert_eq!(
+            statuses,
+            vec![(2, OrderStatus::Completed), (1, OrderStatus::Refunded)]
+        );
+    }
+    let msg = QueryMsg::OrdersByAddress {
+        address: listing_buyer.to_string(),
+        limit: Some(1),
+        start_after: Some(2),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrdersResponse = from_json(&bin).unwrap();
+    assert_eq!(res.orders.len(), 1);
+    assert_eq!(res.orders[0].order_id, 1);
+
+    let msg = QueryMsg::OrdersByAddress {
+        address: instantiator.to_string(),
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: OrdersResponse = from_json(&bin).unwrap();
+    assert!(res.orders.is_empty());
+}
+
+//Test that stock is reserved per order, sells out, and comes back on refund or restock
+#[test]
+fn test_listing_quantity_and_restock() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let first_buyer = deps.api.addr_make("first_buyer");
+    let second_buyer = deps.api.addr_make("second_buyer");
+    let seller_info = message_info(&listing_creator, &[]);
+
+    let create = |quantity| ExecuteMsg::CreateListing {
+        listing_title: "Sticker Pack".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Holographic sticker pack".to_string(),
+        tags: vec!["Stickers".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        price: 1_000_000,
+        denom: JUNO.to_string(),
+        quantity,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), create(0)).unwrap_err();
+    assert_eq!(err.to_string(), "Quantity must be at least 1");
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), create(2)).unwrap();
+
+    let query_listing = |deps: &cosmwasm_std::OwnedDeps<_, _, _>| {
+        let msg = QueryMsg::Listing { listing_id: 1 };
+        let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: ListingResponse = from_json(&bin).unwrap();
+        let listing = res.listing.unwrap();
+        (listing.status, listing.quantity, listing.reserved)
+    };
+
+    //each purchase opens its own order and the last unit sells the listing out
+    for buyer in [&first_buyer, &second_buyer] {
+        let info = message_info(buyer, &[coin(1_000_000, JUNO)]);
+        let msg = ExecuteMsg::Purchase {
+            listing_id: 1,
+            variant_id: None,
+            shipping_id: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+    assert_eq!(query_listing(&deps), (ListingStatus::SoldOut, 0, 2));
+    let info = message_info(&first_buyer, &[coin(1_000_000, JUNO)]);
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Listing 1 is sold out");
+
+    //the listing can't be changed under buyers that have paid
+    let msg = ExecuteMsg::EditListing {
+        listing_id: 1,
+        external_id: IPFS_LINK.to_string(),
+        text: "Matte sticker pack".to_string(),
+        tags: vec![],
+        price: 500_000,
+        variants: None,
+        shipping_options: None,
+        ship_within: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Listing 1 has 2 units reserved by orders in escrow"
+    );
+
+    //a cancelled order puts its unit back on sale, a completed one does not
+    let info = message_info(&first_buyer, &[]);
+    let msg = ExecuteMsg::CancelPurchase { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(query_listing(&deps), (ListingStatus::Active, 1, 1));
+    let msg = ExecuteMsg::SignShipped { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let info = message_info(&second_buyer, &[]);
+    let msg = ExecuteMsg::SignReceived { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(query_listing(&deps), (ListingStatus::Active, 1, 0));
+
+    //only the seller restocks
+    let info = message_info(&first_buyer, &[]);
+    let msg = ExecuteMsg::RestockListing {
+        listing_id: 1,
+        quantity: 3,
+        variant_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+    let zero = ExecuteMsg::RestockListing {
+        listing_id: 1,
+        quantity: 0,
+        variant_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), zero).unwrap_err();
+    assert_eq!(err.to_string(), "Quantity must be at least 1");
+
+    //selling out then restocking reopens the listing
+    let info = message_info(&first_buyer, &[coin(1_000_000, JUNO)]);
+    let purchase = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, purchase).unwrap();
+    assert_eq!(query_listing(&deps), (ListingStatus::SoldOut, 0, 1));
+    let res = execute(deps.as_mut(), env, seller_info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("status", "active")));
+    assert_eq!(query_listing(&deps), (ListingStatus::Active, 3, 1));
+}
+
+//Test that variants are bought, priced and stocked on their own
+#[test]
+fn test_listing_variants() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let listing_buyer = deps.api.addr_make("listing_buyer");
+    let seller_info = message_info(&listing_creator, &[]);
+
+    let variants = |image: &str| {
+        vec![
+            VariantMsg {
+                name: "Small".to_string(),
+                price: 1_000_000,
+                quantity: 1,
+                external_id: None,
+            },
+            VariantMsg {
+                name: "Large".to_string(),
+                price: 1_500_000,
+                quantity: 2,
+                external_id: Some(image.to_string()),
+            },
+        ]
+    };
+    let create = |variants| ExecuteMsg::CreateListing {
+        listing_title: "Band Shirt".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Tour shirt".to_string(),
+        tags: vec!["Apparel".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        price: 0,
+        denom: JUNO.to_string(),
+        quantity: 0,
+        variants,
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    //variant images follow the same gateway rule as the listing
+    let msg = create(variants("https://example.com/large.png"));
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::MustUseJulianGateway { .. }));
+    let msg = create(variants(IPFS_LINK));
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+    //the listing shows the lowest price and total stock
+    let msg = QueryMsg::Listing { listing_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ListingResponse = from_json(&bin).unwrap();
+    let listing = res.listing.unwrap();
+    assert_eq!((listing.price, listing.quantity), (1_000_000, 3));
+    assert_eq!(listing.variants[1].variant_id, 2);
+
+    let purchase = |variant_id| ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id,
+        shipping_id: None,
+    };
+    let info = message_info(&listing_buyer, &[coin(1_000_000, JUNO)]);
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), purchase(None)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Listing 1 has variants, a variant_id is required"
+    );
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), purchase(Some(3))).unwrap_err();
+    assert_eq!(err.to_string(), "Listing 1 has no variant 3");
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), purchase(Some(2))).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Insufficient funds. Needed: 1500000ujuno Sent: 1000000ujuno"
+    );
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), purchase(Some(1))).unwrap();
+    let err = execute(deps.as_mut(), env.clone(), info, purchase(Some(1))).unwrap_err();
+    assert_eq!(err.to_string(), "Variant 1 of listing 1 is sold out");
+
+    //the order and the settlement use the variant's price
+    let info = message_info(&listing_buyer, &[coin(1_500_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info, purchase(Some(2))).unwrap();
+    let msg = QueryMsg::Order { order_id: 2 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    let order = res.order.unwrap();
+    assert_eq!((order.variant_id, order.price), (Some(2), 1_500_000));
+    let msg = ExecuteMsg::SignShipped { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let info = message_info(&listing_buyer, &[]);
+    let msg = ExecuteMsg::SignReceived { order_id: 2 };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("amount to seller", "1425000")));
+
+    //restocking a variant listing names the variant
+    let restock = |variant_id| ExecuteMsg::RestockListing {
+        listing_id: 1,
+        quantity: 2,
+        variant_id,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        restock(None),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::VariantRequired { listing_id: 1 }
+    ));
+    let _res = execute(deps.as_mut(), env.clone(), seller_info, restock(Some(1))).unwrap();
+    let msg = QueryMsg::Listing { listing_id: 1 };
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: ListingResponse = from_json(&bin).unwrap();
+    let listing = res.listing.unwrap();
+    assert_eq!((listing.quantity, listing.reserved), (3, 1));
+    assert_eq!(
+        (listing.variants[0].quantity, listing.variants[0].reserved),
+        (2, 1)
+    );
+    assert_eq!(
+        (listing.variants[1].quantity, listing.variants[1].reserved),
+        (1, 0)
+    );
+}
+
+//Test that an auction takes rising bids, refunds the outbid, extends on late bids and settles into an order
+#[test]
+fn test_english_auction() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let seller = deps.api.addr_make("seller");
+    let first_bidder = deps.api.addr_make("first_bidder");
+    let second_bidder = deps.api.addr_make("second_bidder");
+    let seller_info = message_info(&seller, &[]);
+    let end_time = env.block.time.plus_seconds(3600);
+
+    let create = |min_increment, end_time| ExecuteMsg::CreateAuction {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Rangefinder, fully working".to_string(),
+        tags: vec!["Cameras".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        denom: JUNO.to_string(),
+        start_price: 1_000_000,
+        reserve_price: 2_000_000,
+        min_increment,
+        end_time,
+        extension_seconds: 600,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        create(0, end_time),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Auction needs an end time in the future and a minimum increment of at least 1"
+    );
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        create(100_000, env.block.time),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidAuction {}));
+    let mut msg = create(100_000, end_time);
+    if let ExecuteMsg::CreateAuction { start_price, .. } = &mut msg {
+        *start_price = 0;
+    }
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidPriceSchedule {}));
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        create(100_000, end_time),
+    )
+    .unwrap();
+
+    //auctions are only sold through bids
+    let info = message_info(&first_bidder, &[coin(1_000_000, JUNO)]);
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Listing 1 is a english_auction listing");
+
+    //the first bid must meet the start price, the seller can't bid
+    let bid = |bidder: &Addr, amount| {
+        (
+            message_info(bidder, &[coin(amount, JUNO)]),
+            ExecuteMsg::PlaceBid { listing_id: 1 },
+        )
+    };
+    let (info, msg) = bid(&first_bidder, 999_999);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    let (info, msg) = bid(&seller, 1_000_000);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let (info, msg) = bid(&first_bidder, 1_500_000);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.messages.is_empty());
+    assert!(res
+        .attributes
+        .contains(&attr("end_time", end_time.to_string())));
+
+    //bidding on a listing with bids can't change it
+    let msg = ExecuteMsg::EditListing {
+        listing_id: 1,
+        external_id: IPFS_LINK.to_string(),
+        text: "Rangefinder, shutter sticks".to_string(),
+        tags: vec![],
+        price: 1,
+        variants: None,
+        shipping_options: None,
+        ship_within: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Auction for listing 1 already has bids");
+
+    //the next bid must beat the highest by min_increment, a late one extends the auction
+    let (info, msg) = bid(&second_bidder, 1_550_000);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    env.block.time = end_time.minus_seconds(60);
+    let (info, msg) = bid(&second_bidder, 2_500_000);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let extended_end = env.block.time.plus_seconds(600);
+    assert!(res
+        .attributes
+        .contains(&attr("end_time", extended_end.to_string())));
+    assert!(res
+        .attributes
+        .contains(&attr("outbid", first_bidder.to_string())));
+
+    //the outbid bidder is credited their bid back
+    let msg = QueryMsg::Balance {
+        address: first_bidder.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(1_500_000, JUNO)]);
+
+    let msg = QueryMsg::Bids {
+        listing_id: 1,
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BidsResponse = from_json(&bin).unwrap();
+    let bids: Vec<_> = res
+        .bids
+        .iter()
+        .map(|bid| (bid.bid_id, bid.amount))
+        .collect();
+    assert_eq!(bids, vec![(2, 2_500_000), (1, 1_500_000)]);
+
+    //settling waits for the extended end time and is open to anyone
+    env.block.time = end_time;
+    let info = message_info(&first_bidder, &[]);
+    let msg = ExecuteMsg::SettleAuction { listing_id: 1 };
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::AuctionNotEnded { .. }));
+    let (bid_info, bid_msg) = bid(&first_bidder, 3_000_000);
+    env.block.time = extended_end;
+    let err = execute(deps.as_mut(), env.clone(), bid_info, bid_msg).unwrap_err();
+    assert_eq!(err.to_string(), "Auction for listing 1 has ended");
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("winner", second_bidder.to_string())));
+    assert!(res.attributes.contains(&attr("order_id", "1")));
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidStatusTransition { .. }));
+
+    //the winning bid is escrowed in the order and settles like a purchase
+    let msg = QueryMsg::Order { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    let order = res.order.unwrap();
+    assert_eq!(
+        (order.buyer, order.price, order.status),
+        (second_bidder.clone(), 2_500_000, OrderStatus::Purchased)
+    );
+    let msg = QueryMsg::EscrowSummary {};
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert_eq!(res.denoms[0].escrowed, Uint128::new(2_500_000));
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+    let info = message_info(&second_bidder, &[]);
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = QueryMsg::Balance {
+        address: seller.to_string(),
+    };
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(2_375_000, JUNO)]);
+}
+
+//Test that an auction ending below its reserve refunds the highest bidder and sells nothing
+#[test]
+fn test_english_auction_reserve_not_met() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.push_payouts = true;
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let seller = deps.api.addr_make("seller");
+    let bidder = deps.api.addr_make("bidder");
+    let seller_info = message_info(&seller, &[]);
+    let end_time = env.block.time.plus_seconds(3600);
+    let msg = ExecuteMsg::CreateAuction {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Rangefinder, fully working".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        denom: JUNO.to_string(),
+        start_price: 1_000_000,
+        reserve_price: 2_000_000,
+        min_increment: 1,
+        end_time,
+        extension_seconds: 0,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+    //stray denoms are returned with the bid, the bid itself is everything sent in the auction denom
+    let info = message_info(&bidder, &[coin(1_200_000, JUNO), coin(5, USDC)]);
+    let msg = ExecuteMsg::PlaceBid { listing_id: 1 };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: bidder.to_string(),
+            amount: vec![coin(5, USDC)],
+        })
+    );
+    assert!(res.attributes.contains(&attr("amount", "1200000")));
+
+    //auctions hold a single unit
+    let msg = ExecuteMsg::RestockListing {
+        listing_id: 1,
+        quantity: 1,
+        variant_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::WrongSaleType { .. }));
+    let msg = ExecuteMsg::DeleteListing { listing_id: 1 };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::AuctionHasBids { listing_id: 1 }
+    ));
+
+    env.block.time = end_time;
+    let info = message_info(&instantiator, &[]);
+    let msg = ExecuteMsg::SettleAuction { listing_id: 1 };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("winner", "none")));
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: bidder.to_string(),
+            amount: vec![coin(1_200_000, JUNO)],
+        })
+    );
+
+    let msg = QueryMsg::Listing { listing_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ListingResponse = from_json(&bin).unwrap();
+    assert_eq!(res.listing.unwrap().status, ListingStatus::Ended);
+    let msg = QueryMsg::EscrowSummary {};
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert!(res.denoms.is_empty());
+
+    //an ended auction can be taken down
+    let msg = ExecuteMsg::DeleteListing { listing_id: 1 };
+    let _res = execute(deps.as_mut(), env, seller_info, msg).unwrap();
+}
+
+//Test that a dutch auction charges the scheduled price at purchase time and refunds the rest
+#[test]
+fn test_dutch_auction() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let seller = deps.api.addr_make("seller");
+    let buyer = deps.api.addr_make("buyer");
+    let seller_info = message_info(&seller, &[]);
+    let start = env.block.time;
+
+    let create = |floor_price, step_seconds| ExecuteMsg::CreateDutchAuction {
+        listing_title: "Fresh Flowers".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Bouquet, picked this morning".to_string(),
+        tags: vec!["Flowers".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        denom: JUNO.to_string(),
+        quantity: 2,
+        start_price: 1_000_000,
+        floor_price,
+        end_time: start.plus_seconds(600),
+        step_seconds,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        create(1_000_000, 0),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Price schedule needs a start price above the floor and an end time in the future"
+    );
+    //listing 1 drops every second, listing 2 every 100 seconds
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        create(400_000, 0),
+    )
+    .unwrap();
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        create(400_000, 100),
+    )
+    .unwrap();
+
+    let current_price = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, env: &Env, listing_id| {
+        let msg = QueryMsg::CurrentPrice { listing_id };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: CurrentPriceResponse = from_json(&bin).unwrap();
+        res.price
+    };
+    assert_eq!(current_price(&deps, &env, 1), 1_000_000);
+    env.block.time = start.plus_seconds(150);
+    assert_eq!(current_price(&deps, &env, 1), 850_000);
+    assert_eq!(current_price(&deps, &env, 2), 900_000);
+    let info = message_info(&buyer, &[coin(840_000, JUNO)]);
+    let msg = ExecuteMsg::MakeOffer {
+        listing_id: 1,
+        variant_id: None,
+        price: 840_000,
+        expires_at: start.plus_seconds(3600),
+        shipping_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let haggler = deps.api.addr_make("haggler");
+    let info = message_info(&haggler, &[coin(300_000, JUNO)]);
+    let msg = ExecuteMsg::MakeOffer {
+        listing_id: 1,
+        variant_id: None,
+        price: 300_000,
+        expires_at: start.plus_seconds(3600),
+        shipping_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = ExecuteMsg::CounterOffer {
+        offer_id: 2,
+        price: 800_000,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+
+    //the buyer pays the price at execution and gets the rest back
+    let info = message_info(&buyer, &[coin(1_000_000, JUNO)]);
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 2,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: buyer.to_string(),
+            amount: vec![coin(100_000, JUNO)],
+        })
+    );
+    let msg = QueryMsg::Order { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    assert_eq!(res.order.unwrap().price, 900_000);
+
+    //past the end time the price rests at the floor
+    env.block.time = start.plus_seconds(700);
+    assert_eq!(current_price(&deps, &env, 2), 400_000);
+    let info = message_info(&buyer, &[coin(399_999, JUNO)]);
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 2,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    let info = message_info(&buyer, &[coin(400_000, JUNO)]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("payment_required", "400000")));
+
+    //an offer made above where the price has since decayed is taken at the live price
+    let msg = ExecuteMsg::AcceptOffer { offer_id: 1 };
+    let info = message_info(&seller, &[coin(1, JUNO)]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::UnexpectedFunds {}));
+    let res = execute(deps.as_mut(), env.clone(), message_info(&seller, &[]), msg).unwrap();
+    assert!(res.attributes.contains(&attr("price", "400000")));
+    let msg = QueryMsg::Balance {
+        address: buyer.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(440_000, JUNO)]);
+    let msg = QueryMsg::EscrowSummary {};
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert_eq!(res.denoms[0].escrowed, Uint128::new(2_000_000));
+
+    //a counter accepted after the price decayed below it only needs topping up to the live price
+    let msg = ExecuteMsg::AcceptOffer { offer_id: 2 };
+    let info = message_info(&haggler, &[coin(500_000, JUNO)]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("price", "400000")));
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: haggler.to_string(),
+            amount: vec![coin(400_000, JUNO)],
+        })
+    );
+    let msg = QueryMsg::EscrowSummary {};
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert_eq!(res.denoms[0].escrowed, Uint128::new(2_100_000));
+}
+
+//Test that a sealed bid auction takes hidden commitments, sells to the highest valid reveal and
+//returns deposits, keeping part of the unrevealed ones for the seller
+#[test]
+fn test_sealed_bid_auction() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let seller = deps.api.addr_make("seller");
+    let first = deps.api.addr_make("first_bidder");
+    let second = deps.api.addr_make("second_bidder");
+    let silent = deps.api.addr_make("silent_bidder");
+    let overbid = deps.api.addr_make("overbid_bidder");
+    let seller_info = message_info(&seller, &[]);
+    let start = env.block.time;
+
+    let create = |forfeit_bps| ExecuteMsg::CreateSealedBidAuction {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Rangefinder, fully working".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        denom: JUNO.to_string(),
+        min_deposit: 1_000_000,
+        reserve_price: 1_500_000,
+        commit_end: start.plus_seconds(100),
+        reveal_end: start.plus_seconds(200),
+        forfeit_bps,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        create(10_001),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSealedBidAuction {}));
+    let _res = execute(deps.as_mut(), env.clone(), seller_info, create(1_000)).unwrap();
+
+    let commit = |bidder: &Addr, amount, deposit| {
+        (
+            message_info(bidder, &[coin(deposit, JUNO)]),
+            ExecuteMsg::CommitBid {
+                listing_id: 1,
+                commitment: sealed_bid_commitment(1, bidder, amount, "salt"),
+            },
+        )
+    };
+    let reveal = |bidder: &Addr, amount, salt: &str| {
+        (
+            message_info(bidder, &[]),
+            ExecuteMsg::RevealBid {
+                listing_id: 1,
+                amount,
+                salt: salt.to_string(),
+            },
+        )
+    };
+
+    let (info, msg) = commit(&first, 2_000_000, 999_999);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    for (bidder, amount, deposit) in [
+        (&first, 2_000_000, 3_000_000),
+        (&second, 2_500_000, 2_600_000),
+        (&silent, 1_800_000, 2_000_000),
+        (&overbid, 4_000_000, 1_000_000),
+    ] {
+        let (info, msg) = commit(bidder, amount, deposit);
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+    let (info, msg) = commit(&first, 2_000_000, 3_000_000);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(err.to_string(), "Already committed a bid on listing 1");
+
+    //reveals only open once commitments close
+    let (info, msg) = reveal(&first, 2_000_000, "salt");
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Auction for listing 1 is not taking reveals right now"
+    );
+    env.block.time = start.plus_seconds(150);
+    let (info, msg) = commit(&seller, 2_000_000, 3_000_000);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::WrongAuctionPhase { .. }));
+
+    let (info, msg) = reveal(&first, 2_000_000, "pepper");
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidReveal {}));
+    let (info, msg) = reveal(&first, 2_000_000, "salt");
+    let res = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap();
+    assert!(res.attributes.contains(&attr("valid", "true")));
+    let (info, _) = reveal(&first, 2_000_000, "salt");
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidReveal {}));
+    let (info, msg) = reveal(&second, 2_500_000, "salt");
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    //a bid the deposit can't cover doesn't count
+    let (info, msg) = reveal(&overbid, 4_000_000, "salt");
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("valid", "false")));
+
+    let settle = ExecuteMsg::SettleAuction { listing_id: 1 };
+    let info = message_info(&instantiator, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), settle.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::AuctionNotEnded { .. }));
+
+    //once reveals close losing deposits come back whole and unrevealed ones less the forfeit
+    env.block.time = start.plus_seconds(200);
+    let reclaim = ExecuteMsg::ReclaimDeposit { listing_id: 1 };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&second, &[]),
+        reclaim.clone(),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "The winning bid on listing 1 is paid out through SettleAuction"
+    );
+    for bidder in [&first, &silent] {
+        let info = message_info(bidder, &[]);
+        let _res = execute(deps.as_mut(), env.clone(), info, reclaim.clone()).unwrap();
+    }
+    let balance = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &Addr| {
+        let msg = QueryMsg::Balance {
+            address: address.to_string(),
+        };
+        let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: BalanceResponse = from_json(&bin).unwrap();
+        res.balances
+    };
+    assert_eq!(balance(&deps, &first), vec![coin(3_000_000, JUNO)]);
+    assert_eq!(balance(&deps, &silent), vec![coin(1_800_000, JUNO)]);
+    assert_eq!(balance(&deps, &seller), vec![coin(200_000, JUNO)]);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&first, &[]),
+        reclaim,
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoCommitment { listing_id: 1 }));
+
+    //the winner pays their bid out of the deposit and gets the rest back
+    let res = execute(deps.as_mut(), env.clone(), info, settle).unwrap();
+    assert!(res.attributes.contains(&attr("winner", second.to_string())));
+    assert!(res.attributes.contains(&attr("order_id", "1")));
+    assert_eq!(balance(&deps, &second), vec![coin(100_000, JUNO)]);
+    let msg = QueryMsg::Order { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    assert_eq!(res.order.unwrap().price, 2_500_000);
+
+    //only valid reveals are listed as bids, the unclaimed deposit stays escrowed
+    let msg = QueryMsg::Bids {
+        listing_id: 1,
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BidsResponse = from_json(&bin).unwrap();
+    let bids: Vec<_> = res.bids.iter().map(|bid| bid.bidder.clone()).collect();
+    assert_eq!(bids, vec![second, first]);
+    let msg = QueryMsg::SealedBid {
+        listing_id: 1,
+        bidder: overbid.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: SealedBidResponse = from_json(&bin).unwrap();
+    assert_eq!(res.bid.unwrap().amount, Some(4_000_000));
+    let msg = QueryMsg::EscrowSummary {};
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert_eq!(res.denoms[0].escrowed, Uint128::new(3_500_000));
+}
+
+//Test that offers are escrowed, countered, accepted into orders and refunded when they lose out
+#[test]
+fn test_offers() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let seller = deps.api.addr_make("seller");
+    let haggler = deps.api.addr_make("haggler");
+    let rival = deps.api.addr_make("rival");
+    let lowballer = deps.api.addr_make("lowballer");
+    let seller_info = message_info(&seller, &[]);
+    let create = |quantity| ExecuteMsg::CreateListing {
+        listing_title: format!("Sticker Pack {}", quantity),
+        external_id: IPFS_LINK.to_string(),
+        text: "Holographic sticker pack".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 1_000_000,
+        denom: JUNO.to_string(),
+        quantity,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), create(1)).unwrap();
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), create(2)).unwrap();
+
+    let expires_at = env.block.time.plus_seconds(3600);
+    let offer = |buyer: &Addr, listing_id, price, sent, expires_at| {
+        (
+            message_info(buyer, &[coin(sent, JUNO)]),
+            ExecuteMsg::MakeOffer {
+                listing_id,
+                variant_id: None,
+                price,
+                expires_at,
+                shipping_id: None,
+            },
+        )
+    };
+    let (info, msg) = offer(&haggler, 1, 1_000_000, 1_000_000, expires_at);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Offer price 1000000 must be between 1 and 999999"
+    );
+    let (info, msg) = offer(&haggler, 1, 600_000, 600_000, env.block.time);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidExpiry {}));
+
+    //anything sent beyond the offer goes straight back
+    let (info, msg) = offer(&haggler, 1, 600_000, 700_000, expires_at);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: haggler.to_string(),
+            amount: vec![coin(100_000, JUNO)],
+        })
+    );
+    let (info, msg) = offer(&rival, 1, 700_000, 700_000, expires_at);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let short_expiry = env.block.time.plus_seconds(60);
+    let (info, msg) = offer(&lowballer, 1, 500_000, 500_000, short_expiry);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //only the seller answers offers
+    let msg = ExecuteMsg::RejectOffer { offer_id: 2 };
+    let err = execute(deps.as_mut(), env.clone(), message_info(&rival, &[]), msg).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let counter = |price| ExecuteMsg::CounterOffer { offer_id: 1, price };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        counter(1_100_000),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidOfferPrice { .. }));
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        counter(800_000),
+    )
+    .unwrap();
+
+    //an expired offer can be sent back to its buyer by anyone
+    env.block.time = short_expiry;
+    let msg = ExecuteMsg::WithdrawOffer { offer_id: 3 };
+    let res = execute(deps.as_mut(), env.clone(), message_info(&seller, &[]), msg).unwrap();
+    assert!(res.attributes.contains(&attr("status", "expired")));
+
+    //the buyer takes the counter by topping up, the rival offer is refunded with the listing sold out
+    let accept = ExecuteMsg::AcceptOffer { offer_id: 1 };
+    let info = message_info(&haggler, &[coin(150_000, JUNO)]);
+    let err = execute(deps.as_mut(), env.clone(), info, accept.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    let info = message_info(&haggler, &[coin(200_000, JUNO)]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), accept.clone()).unwrap();
+    assert!(res.attributes.contains(&attr("order_id", "1")));
+    let err = execute(deps.as_mut(), env.clone(), info, accept).unwrap_err();
+    assert_eq!(err.to_string(), "Offer 1 is already accepted");
+
+    let msg = QueryMsg::Order { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    let order = res.order.unwrap();
+    assert_eq!((order.buyer, order.price), (haggler.clone(), 800_000));
+    let msg = QueryMsg::Offer { offer_id: 2 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OfferResponse = from_json(&bin).unwrap();
+    assert_eq!(res.offer.unwrap().status, OfferStatus::Rejected);
+    let msg = QueryMsg::OffersByListing {
+        listing_id: 1,
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OffersResponse = from_json(&bin).unwrap();
+    assert!(res.offers.is_empty());
+    for (buyer, refund) in [(&rival, 700_000), (&lowballer, 500_000)] {
+        let msg = QueryMsg::Balance {
+            address: buyer.to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: BalanceResponse = from_json(&bin).unwrap();
+        assert_eq!(res.balances, vec![coin(refund, JUNO)]);
+    }
+
+    //with stock left the seller takes one offer and competing ones stay open until answered
+    for (buyer, price) in [(&rival, 900_000u64), (&lowballer, 400_000)] {
+        let (info, msg) = offer(buyer, 2, price, price as u128, expires_at);
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+    let msg = ExecuteMsg::AcceptOffer { offer_id: 4 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let msg = QueryMsg::OffersByListing {
+        listing_id: 2,
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OffersResponse = from_json(&bin).unwrap();
+    let open: Vec<_> = res.offers.iter().map(|offer| offer.offer_id).collect();
+    assert_eq!(open, vec![5]);
+    let msg = ExecuteMsg::RejectOffer { offer_id: 5 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+
+    let msg = QueryMsg::EscrowSummary {};
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert_eq!(res.denoms[0].escrowed, Uint128::new(1_700_000));
+}
+
+//Test that a wanted ad escrows its budget, takes proposals and turns the chosen one into an order
+#[test]
+fn test_wanted_ads() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let requester = deps.api.addr_make("requester");
+    let first_seller = deps.api.addr_make("first_seller");
+    let second_seller = deps.api.addr_make("second_seller");
+    let post = |title: &str, tags: Vec<&str>| ExecuteMsg::PostWantedAd {
+        title: title.to_string(),
+        text: "Looking for one in working condition".to_string(),
+        tags: tags.into_iter().map(String::from).collect(),
+        denom: JUNO.to_string(),
+        budget: 1_000_000,
+    };
+    let info = message_info(&requester, &[coin(999_999, JUNO)]);
+    let err = execute(deps.as_mut(), env.clone(), info, post("Camera", vec![])).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    let info = message_info(&requester, &[coin(1_000_000, JUNO)]);
+    for title in [" ".to_string(), "Camera".repeat(30)] {
+        let err = execute(
+            deps.as_mut(),
+            env.clone(),
+            info.clone(),
+            post(&title, vec![]),
+        )
+        .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Titles must be between 1 and 128 characters"
+        );
+    }
+    let msg = post("Film Camera", vec!["Cameras", "Film"]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = post("Lens", vec!["Cameras"]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let propose = |price| ExecuteMsg::ProposeFulfilment {
+        wanted_ad_id: 1,
+        price,
+        text: "Rangefinder, fully working".to_string(),
+        external_id: Some(IPFS_LINK.to_string()),
+        contact: "Signal: +1234567890".to_string(),
+    };
+    let info = message_info(&first_seller, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), propose(1_000_001)).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Proposal price 1000001 must be between 1 and the budget of 1000000"
+    );
+    let msg = ExecuteMsg::ProposeFulfilment {
+        wanted_ad_id: 1,
+        price: 900_000,
+        text: "Rangefinder, fully working".to_string(),
+        external_id: None,
+        contact: "Signal: +1234567890 ".repeat(7),
+    };
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Contact details must be between 1 and 128 characters"
+    );
+    let info_requester = message_info(&requester, &[]);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info_requester.clone(),
+        propose(900_000),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let _res = execute(deps.as_mut(), env.clone(), info, propose(900_000)).unwrap();
+    let info = message_info(&second_seller, &[]);
+    let _res = execute(deps.as_mut(), env.clone(), info, propose(750_000)).unwrap();
+
+    let msg = QueryMsg::Proposals {
+        wanted_ad_id: 1,
+        limit: None,
+        start_after: Some(1),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ProposalsResponse = from_json(&bin).unwrap();
+    assert_eq!(res.proposals.len(), 1);
+    assert_eq!(res.proposals[0].seller, second_seller);
+
+    //only the requester picks, the order is paid from the budget and the rest refunded
+    let accept = ExecuteMsg::AcceptProposal {
+        wanted_ad_id: 1,
+        proposal_id: 2,
+    };
+    let info = message_info(&second_seller, &[]);
+    let err = execute(deps.as_mut(), env.clone(), info, accept.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info_requester.clone(),
+        accept.clone(),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("order_id", "1")));
+    let err = execute(deps.as_mut(), env.clone(), info_requester.clone(), accept).unwrap_err();
+    assert_eq!(err.to_string(), "Wanted ad 1 is already filled");
+
+    let msg = QueryMsg::Order { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    let order = res.order.unwrap();
+    assert_eq!(
+        (order.listing_id, order.wanted_ad_id, order.price),
+        (None, Some(1), 750_000)
+    );
+
+    //the order follows the usual escrow flow
+    let info = message_info(&second_seller, &[]);
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("wanted_ad_id", "1")));
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), info_requester.clone(), msg).unwrap();
+
+    //filled ads leave the tag index but stay in the requester's history
+    let by_tag = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, tag: &str| {
+        let msg = QueryMsg::WantedAdsByTag {
+            tag: tag.to_string(),
+            limit: None,
+            start_after: None,
+        };
+        let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: WantedAdsResponse = from_json(&bin).unwrap();
+        res.wanted_ads
+            .iter()
+            .map(|wanted_ad| wanted_ad.wanted_ad_id)
+            .collect::<Vec<_>>()
+    };
+    assert_eq!(by_tag(&deps, "Cameras"), vec![2]);
+    assert!(by_tag(&deps, "Film").is_empty());
+
+    let msg = ExecuteMsg::CancelWantedAd { wanted_ad_id: 2 };
+    let res = execute(deps.as_mut(), env.clone(), info_requester, msg).unwrap();
+    assert!(res.messages.is_empty());
+    assert!(by_tag(&deps, "Cameras").is_empty());
+    let msg = QueryMsg::WantedAdsByRequester {
+        requester: requester.to_string(),
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: WantedAdsResponse = from_json(&bin).unwrap();
+    let statuses: Vec<_> = res
+        .wanted_ads
+        .iter()
+        .map(|wanted_ad| wanted_ad.status)
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![WantedAdStatus::Cancelled, WantedAdStatus::Filled]
+    );
+
+    //the leftover budget and the cancelled one are credited back, nothing stays escrowed
+    let msg = QueryMsg::Balance {
+        address: requester.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(1_250_000, JUNO)]);
+    let msg = QueryMsg::EscrowSummary {};
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert!(res.denoms[0].escrowed.is_zero());
+}
+
+//Test buying a cart of listings in one payment, with a seller's orders sharing one shipment
+#[test]
+fn test_purchase_many() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.accepted_denoms = vec![JUNO.to_string(), USDC.to_string()];
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let print_seller = deps.api.addr_make("print_seller");
+    let kit_seller = deps.api.addr_make("kit_seller");
+    let buyer = deps.api.addr_make("buyer");
+    let create = |title: &str, price, denom: &str, quantity| ExecuteMsg::CreateListing {
+        listing_title: title.to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Shipped in a padded envelope".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price,
+        denom: denom.to_string(),
+        quantity,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let info = message_info(&print_seller, &[]);
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        create("Poster", 1_000_000, JUNO, 1),
+    )
+    .unwrap();
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        create("Postcard", 500_000, JUNO, 2),
+    )
+    .unwrap();
+    let info = message_info(&kit_seller, &[]);
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        create("Keyboard Kit", 2_000_000, JUNO, 1),
+    )
+    .unwrap();
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        create("Keycap Set", 1_000_000, USDC, 1),
+    )
+    .unwrap();
+
+    let purchase_many = |listing_ids: Vec<u64>| ExecuteMsg::PurchaseMany {
+        listing_ids,
+        group_by_seller: true,
+        region: None,
+    };
+    let info = message_info(&buyer, &[coin(5_000_000, JUNO)]);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        purchase_many(vec![]),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::EmptyCart {}));
+    let err = execute(deps.as_mut(), env.clone(), info, purchase_many(vec![1, 4])).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Every listing in a cart must be priced in ujuno, found ibc/usdc"
+    );
+
+    //the whole cart is checked against the funds at once
+    let cart = vec![1, 2, 2, 3];
+    let info = message_info(&buyer, &[coin(3_999_999, JUNO)]);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        purchase_many(cart.clone()),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    let info = message_info(&buyer, &[coin(4_100_000, JUNO)]);
+    let res = execute(deps.as_mut(), env.clone(), info, purchase_many(cart)).unwrap();
+    assert!(res.attributes.contains(&attr("order_ids", "1,2,3,4")));
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: buyer.to_string(),
+            amount: vec![coin(100_000, JUNO)],
+        })
+    );
+
+    let load_order = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, order_id| {
+        let msg = QueryMsg::Order { order_id };
+        let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: OrderResponse = from_json(&bin).unwrap();
+        res.order.unwrap()
+    };
+    let shipments: Vec<_> = (1..=4)
+        .map(|order_id| load_order(&deps, order_id).shipment_id)
+        .collect();
+    assert_eq!(shipments, vec![Some(1), Some(1), Some(1), None]);
+    let msg = QueryMsg::Listing { listing_id: 2 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ListingResponse = from_json(&bin).unwrap();
+    assert_eq!(res.listing.unwrap().status, ListingStatus::SoldOut);
+
+    //one SignShipped ships the rest of the shipment, leaving out what the buyer cancelled
+    let msg = ExecuteMsg::CancelPurchase { order_id: 3 };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&buyer, &[]), msg).unwrap();
+    let msg = ExecuteMsg::SignShipped { order_id: 2 };
+    let info = message_info(&print_seller, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("shipped_orders", "2,1")));
+    let statuses: Vec<_> = (1..=4)
+        .map(|order_id| load_order(&deps, order_id).status)
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![
+            OrderStatus::Shipped,
+            OrderStatus::Shipped,
+            OrderStatus::Refunded,
+            OrderStatus::Purchased
+        ]
+    );
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&buyer, &[]), msg).unwrap();
+
+    //a sold out listing fails the cart
+    let info = message_info(&buyer, &[coin(1_500_000, JUNO)]);
+    let err = execute(deps.as_mut(), env, info, purchase_many(vec![3, 1])).unwrap_err();
+    assert!(matches!(err, ContractError::SoldOut { listing_id: 3 }));
+}
+
+//Test that the buyer's shipping option is escrowed with the price and paid to the seller without a fee
+#[test]
+fn test_shipping_options() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let seller = deps.api.addr_make("seller");
+    let buyer = deps.api.addr_make("buyer");
+    let shipping = |name: &str, price, regions: Vec<&str>, estimated_days| ShippingOptionMsg {
+        name: name.to_string(),
+        price,
+        regions: regions.into_iter().map(String::from).collect(),
+        estimated_days,
+    };
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Screen Print".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Signed and numbered".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 1_000_000,
+        denom: JUNO.to_string(),
+        quantity: 3,
+        variants: vec![],
+        shipping_options: vec![
+            shipping("Standard", 100_000, vec!["eu", "us"], 7),
+            shipping("Express", 300_000, vec!["eu"], 2),
+            shipping("Overseas", 200_000, vec!["asia"], 14),
+        ],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&seller, &[]), msg).unwrap();
+
+    let purchase = |shipping_id| ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id,
+    };
+    let info = message_info(&buyer, &[coin(1_299_999, JUNO)]);
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), purchase(None)).unwrap_err();
+    assert_eq!(err.to_string(), "Listing 1 needs a shipping option");
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), purchase(Some(4))).unwrap_err();
+    assert_eq!(err.to_string(), "Listing 1 has no shipping option 4");
+    let err = execute(deps.as_mut(), env.clone(), info, purchase(Some(2))).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    let info = message_info(&buyer, &[coin(1_300_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info, purchase(Some(2))).unwrap();
+
+    let load_order = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, order_id| {
+        let msg = QueryMsg::Order { order_id };
+        let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: OrderResponse = from_json(&bin).unwrap();
+        res.order.unwrap()
+    };
+    let order = load_order(&deps, 1);
+    assert_eq!(order.price, 1_000_000);
+    assert_eq!(order.shipping.unwrap().name, "Express");
+
+    //the fee is only taken on the item, shipping goes to the seller in full
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&seller, &[]), msg).unwrap();
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
+    let res = execute(deps.as_mut(), env.clone(), message_info(&buyer, &[]), msg).unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("amount to seller", "1250000")));
+    assert!(res
+        .attributes
+        .contains(&attr("shipping to seller", "300000")));
+    assert!(res.attributes.contains(&attr("fee total", "50000")));
+
+    //refunds return the shipping with the price
+    let info = message_info(&buyer, &[coin(1_100_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info, purchase(Some(1))).unwrap();
+    let msg = ExecuteMsg::CancelPurchase { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&buyer, &[]), msg).unwrap();
+
+    //offers escrow the shipping too, it isn't part of the haggling
+    let msg = ExecuteMsg::MakeOffer {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: Some(1),
+        price: 800_000,
+        expires_at: env.block.time.plus_seconds(3600),
+    };
+    let info = message_info(&buyer, &[coin(899_999, JUNO)]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::NotEnoughFunds { .. }));
+    let info = message_info(&buyer, &[coin(900_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = ExecuteMsg::WithdrawOffer { offer_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&buyer, &[]), msg).unwrap();
+
+    //carts ship each listing with its cheapest option to the buyer's region
+    let purchase_many = |region: &str| ExecuteMsg::PurchaseMany {
+        listing_ids: vec![1],
+        group_by_seller: false,
+        region: Some(region.to_string()),
+    };
+    let info = message_info(&buyer, &[coin(1_200_000, JUNO)]);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        info.clone(),
+        purchase_many("mars"),
+    )
+    .unwrap_err();
+    assert_eq!(err.to_string(), "Listing 1 doesn't ship to mars");
+    let _res = execute(deps.as_mut(), env.clone(), info, purchase_many("asia")).unwrap();
+    assert_eq!(load_order(&deps, 3).shipping.unwrap().shipping_id, 3);
+
+    let balance = |deps: &cosmwasm_std::OwnedDeps<_, _, _>, address: &Addr| {
+        let msg = QueryMsg::Balance {
+            address: address.to_string(),
+        };
+        let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: BalanceResponse = from_json(&bin).unwrap();
+        res.balances
+    };
+    assert_eq!(balance(&deps, &seller), vec![coin(1_250_000, JUNO)]);
+    assert_eq!(balance(&deps, &buyer), vec![coin(2_000_000, JUNO)]);
+    let msg = QueryMsg::EscrowSummary {};
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert_eq!(res.denoms[0].escrowed, Uint128::new(1_200_000));
+}
+
+//Test that a service order pays out milestone by milestone and disputes settle a single milestone
+#[test]
+fn test_service_milestones() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let arbiter_info = info;
+
+    let designer = deps.api.addr_make("designer");
+    let client = deps.api.addr_make("client");
+    let designer_info = message_info(&designer, &[]);
+    let client_info = message_info(&client, &[]);
+    let create = |amounts: Vec<u64>| ExecuteMsg::CreateServiceListing {
+        listing_title: "Logo Design".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Logo with two rounds of revisions".to_string(),
+        tags: vec!["Design".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        denom: JUNO.to_string(),
+        quantity: 2,
+        milestones: amounts
+            .into_iter()
+            .zip(["Sketches", "Final files"])
+            .map(|(amount, description)| MilestoneMsg {
+                description: description.to_string(),
+                amount,
+            })
+            .collect(),
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        designer_info.clone(),
+        create(vec![300_000, 0]),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidMilestones {}));
+    let msg = create(vec![300_000, 700_000]);
+    let _res = execute(deps.as_mut(), env.clone(), designer_info.clone(), msg).unwrap();
+    let msg = QueryMsg::Listing { listing_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ListingResponse = from_json(&bin).unwrap();
+    assert_eq!(res.listing.unwrap().price, 1_000_000);
+
+    //services are bought outright at the sum of their milestones
+    let msg = ExecuteMsg::MakeOffer {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+        price: 800_000,
+        expires_at: env.block.time.plus_seconds(3600),
+    };
+    let info = message_info(&client, &[coin(800_000, JUNO)]);
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::WrongSaleType { .. }));
+    let purchase = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let info = message_info(&client, &[coin(1_000_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info, purchase.clone()).unwrap();
+
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let err = execute(deps.as_mut(), env.clone(), designer_info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Order 1 is settled milestone by milestone");
+    let deliver = |milestone_id| ExecuteMsg::DeliverMilestone {
+        order_id: 1,
+        milestone_id,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        designer_info.clone(),
+        deliver(2),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::MilestoneOutOfOrder { .. }));
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        designer_info.clone(),
+        deliver(1),
+    )
+    .unwrap();
+    let msg = ExecuteMsg::CancelPurchase { order_id: 1 };
+    let err = execute(deps.as_mut(), env.clone(), client_info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidOrderTransition { .. }));
+
+    //approving releases just that milestone, less the fee
+    let approve = |order_id, milestone_id| ExecuteMsg::ApproveMilestone {
+        order_id,
+        milestone_id,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        designer_info.clone(),
+        approve(1, 1),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        client_info.clone(),
+        approve(1, 1),
+    )
+    .unwrap();
+    assert!(res.attributes.contains(&attr("amount to seller", "285000")));
+    assert!(res.attributes.contains(&attr("status", "shipped")));
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        client_info.clone(),
+        approve(1, 2),
+    )
+    .unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Milestone 2 of order 1 cannot go from pending to released"
+    );
+    //settlements are only for delivered or disputed milestones
+    for (milestone_id, status) in [(1, "released"), (2, "pending")] {
+        let msg = ExecuteMsg::ProposeSettlement {
+            order_id: 1,
+            milestone_id: Some(milestone_id),
+            buyer_share_bps: 5_000,
+        };
+        let err = execute(deps.as_mut(), env.clone(), client_info.clone(), msg).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            format!(
+                "Milestone {} of order 1 cannot go from {} to released",
+                milestone_id, status
+            )
+        );
+    }
+
+    //a dispute names the milestone and the arbiter settles only that one
+    let dispute = |milestone_id| ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        client_info.clone(),
+        dispute(None),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ServiceOrder { order_id: 1 }));
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        client_info.clone(),
+        dispute(Some(2)),
+    )
+    .unwrap();
+    let msg = QueryMsg::ArbitrationListings {
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ArbitrationListingsResponse = from_json(&bin).unwrap();
+    assert_eq!(res.orders.len(), 1);
+    let arbitrate = |milestone_id| ExecuteMsg::Arbitrate {
+        order_id: 1,
+        ruling: RulingMsg {
+            buyer_bps: 10_000,
+            seller_bps: 0,
+            arbiter_bps: 0,
+        },
+        milestone_id,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        arbiter_info.clone(),
+        arbitrate(None),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::ServiceOrder { order_id: 1 }));
+    let res = execute(deps.as_mut(), env.clone(), arbiter_info, arbitrate(Some(2))).unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("milestone_status", "refunded")));
+    assert!(res.attributes.contains(&attr("status", "completed")));
+    let msg = QueryMsg::Order { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    let statuses: Vec<_> = res
+        .order
+        .unwrap()
+        .milestones
+        .iter()
+        .map(|milestone| milestone.status)
+        .collect();
+    assert_eq!(
+        statuses,
+        vec![MilestoneStatus::Released, MilestoneStatus::Refunded]
+    );
+
+    //a seller walking away only refunds what hasn't been released
+    let info = message_info(&client, &[coin(1_000_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info, purchase).unwrap();
+    let msg = ExecuteMsg::DeliverMilestone {
+        order_id: 2,
+        milestone_id: 1,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), designer_info.clone(), msg).unwrap();
+    let _res = execute(deps.as_mut(), env.clone(), client_info, approve(2, 1)).unwrap();
+    let msg = ExecuteMsg::CreateProfile {
+        profile_name: "designer".to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), designer_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::SellerCancelSale { order_id: 2 };
+    let res = execute(deps.as_mut(), env.clone(), designer_info, msg).unwrap();
+    assert!(res.attributes.contains(&attr("refund_amount", "700000")));
+    //having paid out a milestone the order completes and counts, without restocking the unit
+    let msg = QueryMsg::Order { order_id: 2 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    assert_eq!(res.order.unwrap().status, OrderStatus::Completed);
+    let msg = QueryMsg::Profile {
+        address: designer.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ProfileResponse = from_json(&bin).unwrap();
+    assert_eq!(res.profile.unwrap().transaction_count, 1);
+    let msg = QueryMsg::Listing { listing_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ListingResponse = from_json(&bin).unwrap();
+    assert_eq!(res.listing.unwrap().quantity, 0);
+
+    let balance = |address: &Addr| {
+        let msg = QueryMsg::Balance {
+            address: address.to_string(),
+        };
+        let bin = query(deps.as_ref(), mock_env(), msg).unwrap();
+        let res: BalanceResponse = from_json(&bin).unwrap();
+        res.balances
+    };
+    assert_eq!(balance(&designer), vec![coin(570_000, JUNO)]);
+    assert_eq!(balance(&client), vec![coin(1_400_000, JUNO)]);
+    let bin = query(deps.as_ref(), env, QueryMsg::EscrowSummary {}).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert!(res.denoms[0].escrowed.is_zero());
+}
+
+//Test that anyone can release a shipped order once the buyer's confirmation window has passed
+#[test]
+fn test_claim_after_timeout() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.confirmation_window = 0;
+    let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "confirmation_window must be at least one second"
+    );
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::UpdateConfig {
+        fee_bps: None,
+        fee_recipients: None,
+        accepted_denoms: None,
+        gateway: None,
+        max_text_length: None,
+        max_id_length: None,
+        push_payouts: None,
+        confirmation_window: Some(0),
+        ship_window: None,
+        max_arbiter_fee_bps: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidWindow { .. }));
+
+    let seller = deps.api.addr_make("seller");
+    let buyer = deps.api.addr_make("buyer");
+    let anyone = message_info(&deps.api.addr_make("anyone"), &[]);
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Hardware Kit".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Soldering required".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 1_000_000,
+        denom: JUNO.to_string(),
+        quantity: 2,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let seller_info = message_info(&seller, &[]);
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let info = message_info(&buyer, &[coin(1_000_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //the window only starts once the seller ships
+    let claim = |order_id| ExecuteMsg::ClaimAfterTimeout { order_id };
+    let err = execute(deps.as_mut(), env.clone(), anyone.clone(), claim(1)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidOrderTransition { .. }));
+    for order_id in [1, 2] {
+        let msg = ExecuteMsg::SignShipped { order_id };
+        let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    }
+    env.block.time = env.block.time.plus_seconds(14 * 24 * 60 * 60 - 1);
+    let err = execute(deps.as_mut(), env.clone(), anyone.clone(), claim(1)).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::ConfirmationWindowOpen { order_id: 1, .. }
+    ));
+
+    //a dispute opened inside the window keeps the order with the arbiter
+    let msg = ExecuteMsg::RequestArbitration {
+        order_id: 2,
+        milestone_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&buyer, &[]), msg).unwrap();
+    env.block.time = env.block.time.plus_seconds(1);
+
+    //an order shipped before it had a window is never claimable
+    let shipped = ORDERS.load(deps.as_ref().storage, 1).unwrap();
+    let mut legacy = shipped.clone();
+    legacy.confirm_by = None;
+    ORDERS.save(deps.as_mut().storage, 1, &legacy).unwrap();
+    let err = execute(deps.as_mut(), env.clone(), anyone.clone(), claim(1)).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::NoConfirmationWindow { order_id: 1 }
+    ));
+    ORDERS.save(deps.as_mut().storage, 1, &shipped).unwrap();
+    let res = execute(deps.as_mut(), env.clone(), anyone.clone(), claim(1)).unwrap();
+    assert!(res.attributes.contains(&attr("status", "completed")));
+    assert!(res.attributes.contains(&attr("amount to seller", "950000")));
+    let err = execute(deps.as_mut(), env.clone(), anyone, claim(2)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidOrderTransition { .. }));
+
+    let msg = QueryMsg::Balance {
+        address: seller.to_string(),
+    };
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(950_000, JUNO)]);
+}
+
+//Test that an order the seller doesn't ship by its deadline can be refunded by anyone
+#[test]
+fn test_expire_unshipped() {
+    let mut deps = mock_dependencies();
+    let mut env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
 
-const JUNO: &str = "ujuno";
-const IPFS_LINK: &str =
-    "https://gateway.pinata.cloud/ipfs/QmQSXMeJRyodyVESWVXT8gd7kQhjrV7sguLnsrXSd6YzvT";
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.ship_window = 0;
+    let err = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "ship_window must be at least one second");
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::UpdateConfig {
+        fee_bps: None,
+        fee_recipients: None,
+        accepted_denoms: None,
+        gateway: None,
+        max_text_length: None,
+        max_id_length: None,
+        push_payouts: None,
+        confirmation_window: None,
+        ship_window: Some(0),
+        max_arbiter_fee_bps: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidWindow { .. }));
 
-//Test that the contract is instantiated correctly
+    let seller = deps.api.addr_make("seller");
+    let buyer = deps.api.addr_make("buyer");
+    let anyone = message_info(&deps.api.addr_make("anyone"), &[]);
+    let seller_info = message_info(&seller, &[]);
+    let msg = ExecuteMsg::CreateProfile {
+        profile_name: "seller".to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let create = |ship_within| ExecuteMsg::CreateListing {
+        listing_title: "Sticker Pack".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Ten vinyl stickers".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 1_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: Some(ship_within),
+    };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), create(0)).unwrap_err();
+    assert_eq!(err.to_string(), "ship_within must be at least one second");
+    let msg = create(2 * 24 * 60 * 60);
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::EditListing {
+        listing_id: 1,
+        external_id: IPFS_LINK.to_string(),
+        text: "Ten vinyl stickers".to_string(),
+        tags: vec![],
+        price: 1_000_000,
+        variants: None,
+        shipping_options: None,
+        ship_within: Some(0),
+    };
+    let err = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidWindow { .. }));
+    let purchase = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let info = message_info(&buyer, &[coin(1_000_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), purchase.clone()).unwrap();
+
+    let expire = |order_id| ExecuteMsg::ExpireUnshipped { order_id };
+    env.block.time = env.block.time.plus_seconds(2 * 24 * 60 * 60 - 1);
+    let err = execute(deps.as_mut(), env.clone(), anyone.clone(), expire(1)).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::ShipByNotPassed { order_id: 1, .. }
+    ));
+    env.block.time = env.block.time.plus_seconds(1);
+    let res = execute(deps.as_mut(), env.clone(), anyone.clone(), expire(1)).unwrap();
+    assert!(res.attributes.contains(&attr("seller", seller.to_string())));
+
+    //the unit goes back on sale and the failure is on the seller's record
+    let msg = QueryMsg::Listing { listing_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ListingResponse = from_json(&bin).unwrap();
+    let listing = res.listing.unwrap();
+    assert_eq!(listing.status, ListingStatus::Active);
+    assert_eq!(listing.quantity, 1);
+    let msg = QueryMsg::Profile {
+        address: seller.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ProfileResponse = from_json(&bin).unwrap();
+    assert_eq!(res.profile.unwrap().failed_shipments, 1);
+    let msg = QueryMsg::Balance {
+        address: buyer.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: BalanceResponse = from_json(&bin).unwrap();
+    assert_eq!(res.balances, vec![coin(1_000_000, JUNO)]);
+
+    //shipping in time keeps the order out of reach
+    let _res = execute(deps.as_mut(), env.clone(), info, purchase).unwrap();
+    let msg = ExecuteMsg::SignShipped { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info, msg).unwrap();
+    env.block.time = env.block.time.plus_seconds(2 * 24 * 60 * 60);
+    let err = execute(deps.as_mut(), env, anyone, expire(2)).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidOrderTransition { .. }));
+}
+
+//Test that a split ruling pays each party its share and is kept for later queries
 #[test]
-fn test_instantiate() {
+fn test_split_ruling() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let arbiter_info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), arbiter_info.clone(), msg).unwrap();
+
+    let seller = deps.api.addr_make("seller");
+    let buyer = deps.api.addr_make("buyer");
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Vintage Amp".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Tubes replaced last year".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 899_999,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![ShippingOptionMsg {
+            name: "Freight".to_string(),
+            price: 100_000,
+            regions: vec!["eu".to_string()],
+            estimated_days: 5,
+        }],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&seller, &[]), msg).unwrap();
+    for (address, profile_name) in [(&seller, "amp_seller"), (&buyer, "amp_buyer")] {
+        let msg = ExecuteMsg::CreateProfile {
+            profile_name: profile_name.to_string(),
+        };
+        let _res = execute(deps.as_mut(), env.clone(), message_info(address, &[]), msg).unwrap();
+    }
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: Some(1),
+    };
+    let info = message_info(&buyer, &[coin(999_999, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&seller, &[]), msg).unwrap();
+    let msg = ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&buyer, &[]), msg).unwrap();
+
+    //the item arrived damaged, most of it goes back to the buyer
+    let arbitrate = |buyer_bps, seller_bps, arbiter_bps| ExecuteMsg::Arbitrate {
+        order_id: 1,
+        ruling: RulingMsg {
+            buyer_bps,
+            seller_bps,
+            arbiter_bps,
+        },
+        milestone_id: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        arbiter_info.clone(),
+        arbitrate(6_000, 3_000, 1_001),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidRuling {}));
+
+    //an arbiter allowed the whole amount still can't rule that neither side gets anything
+    let umpire = deps.api.addr_make("umpire");
+    let msg = ExecuteMsg::UpdateConfig {
+        fee_bps: None,
+        fee_recipients: None,
+        accepted_denoms: None,
+        gateway: None,
+        max_text_length: None,
+        max_id_length: None,
+        push_payouts: None,
+        confirmation_window: None,
+        ship_window: None,
+        max_arbiter_fee_bps: Some(10_000),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), arbiter_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::AddArbiter {
+        address: umpire.to_string(),
+        name: "Umpire".to_string(),
+        fee_bps: 10_000,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), arbiter_info.clone(), msg).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        message_info(&umpire, &[]),
+        arbitrate(0, 0, 10_000),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::RulingPaysNoParty {}));
+
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        arbiter_info,
+        arbitrate(6_000, 3_000, 1_000),
+    )
+    .unwrap();
+    //rounding dust goes to the buyer, the seller pays the fee on all but its share of shipping
+    assert!(res.attributes.contains(&attr("to buyer", "600001")));
+    assert!(res.attributes.contains(&attr("amount to seller", "286499")));
+    assert!(res.attributes.contains(&attr("fee total", "13500")));
+    assert!(res.attributes.contains(&attr("to arbiter", "99999")));
+    assert!(res.attributes.contains(&attr("status", "completed")));
+
+    let msg = QueryMsg::Rulings { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: RulingsResponse = from_json(&bin).unwrap();
+    assert_eq!(res.rulings.len(), 1);
+    let ruling = &res.rulings[0];
+    assert_eq!(ruling.arbiter, instantiator);
+    assert_eq!(ruling.milestone_id, None);
+    assert_eq!(
+        (
+            ruling.buyer_amount,
+            ruling.seller_amount,
+            ruling.arbiter_amount
+        ),
+        (600_001, 299_999, 99_999)
+    );
+    for (address, amount) in [
+        (&buyer, 600_001),
+        (&seller, 286_499),
+        (&instantiator, 113_499),
+    ] {
+        let msg = QueryMsg::Balance {
+            address: address.to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: BalanceResponse = from_json(&bin).unwrap();
+        assert_eq!(res.balances, vec![coin(amount, JUNO)]);
+    }
+    //a ruling that pays the seller counts as a sale for both sides
+    for address in [&seller, &buyer] {
+        let msg = QueryMsg::Profile {
+            address: address.to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: ProfileResponse = from_json(&bin).unwrap();
+        assert_eq!(res.profile.unwrap().transaction_count, 1);
+    }
+}
+
+//Test that buyer and seller can settle a dispute between themselves
+#[test]
+fn test_mutual_settlement() {
     let mut deps = mock_dependencies();
     let env = mock_env();
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
-    let res = instantiate(deps.as_mut(), env, info, msg).unwrap();
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
+    let arbiter_info = info;
+
+    let seller = deps.api.addr_make("seller");
+    let buyer = deps.api.addr_make("buyer");
+    let seller_info = message_info(&seller, &[]);
+    let buyer_info = message_info(&buyer, &[]);
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Mechanical Keyboard".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Brown switches".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 900_000,
+        denom: JUNO.to_string(),
+        quantity: 2,
+        variants: vec![],
+        shipping_options: vec![ShippingOptionMsg {
+            name: "Tracked".to_string(),
+            price: 100_000,
+            regions: vec!["eu".to_string()],
+            estimated_days: 3,
+        }],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: Some(1),
+    };
+    let info = message_info(&buyer, &[coin(1_000_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let propose = |order_id, buyer_share_bps| ExecuteMsg::ProposeSettlement {
+        order_id,
+        milestone_id: None,
+        buyer_share_bps,
+    };
+    let accept = |buyer_share_bps| ExecuteMsg::AcceptSettlement {
+        order_id: 1,
+        milestone_id: None,
+        buyer_share_bps,
+    };
+    //an unshipped order is cancelled instead
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        propose(2, 5_000),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidOrderTransition { .. }));
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg).unwrap();
+
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        propose(1, 10_001),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::InvalidSettlementShare {}));
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        propose(1, 5_000),
+    )
+    .unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        accept(5_000),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        buyer_info.clone(),
+        accept(4_000),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::SettlementChanged {
+            order_id: 1,
+            buyer_share_bps: 5_000
+        }
+    ));
+
+    //a revoked proposal can't be accepted, the buyer counters instead
+    let msg = ExecuteMsg::RevokeSettlement {
+        order_id: 1,
+        milestone_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), buyer_info.clone(), msg.clone()).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        buyer_info.clone(),
+        accept(5_000),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::NoSettlement { order_id: 1 }));
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        buyer_info.clone(),
+        propose(1, 3_000),
+    )
+    .unwrap();
+    let msg = QueryMsg::Settlements { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: SettlementsResponse = from_json(&bin).unwrap();
+    assert_eq!(res.settlements.len(), 1);
+    assert_eq!(res.settlements[0].proposer, buyer);
+
+    //accepting pays both sides and takes the order out of arbitration, the seller's share of
+    //shipping isn't charged the fee
+    let res = execute(
+        deps.as_mut(),
+        env.clone(),
+        seller_info.clone(),
+        accept(3_000),
+    )
+    .unwrap();
+    assert!(res
+        .attributes
+        .contains(&attr("arbitration_cancelled", "true")));
+    assert!(res.attributes.contains(&attr("to buyer", "300000")));
+    assert!(res.attributes.contains(&attr("amount to seller", "668500")));
+    assert!(res.attributes.contains(&attr("fee total", "31500")));
+    assert!(res.attributes.contains(&attr("status", "completed")));
+    let msg = QueryMsg::ArbitrationListings {
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ArbitrationListingsResponse = from_json(&bin).unwrap();
+    assert!(res.orders.is_empty());
+    let msg = ExecuteMsg::Arbitrate {
+        order_id: 1,
+        ruling: RulingMsg {
+            buyer_bps: 10_000,
+            seller_bps: 0,
+            arbiter_bps: 0,
+        },
+        milestone_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), arbiter_info, msg).unwrap_err();
+    assert!(matches!(err, ContractError::InvalidOrderTransition { .. }));
+
+    let msg = QueryMsg::Settlements { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: SettlementsResponse = from_json(&bin).unwrap();
+    assert!(res.settlements.is_empty());
+    for (address, amount) in [(&buyer, 300_000), (&seller, 668_500)] {
+        let msg = QueryMsg::Balance {
+            address: address.to_string(),
+        };
+        let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+        let res: BalanceResponse = from_json(&bin).unwrap();
+        assert_eq!(res.balances, vec![coin(amount, JUNO)]);
+    }
+
+    //a proposal still open when the order settles some other way goes with it
+    let msg = ExecuteMsg::SignShipped { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), seller_info.clone(), msg).unwrap();
+    let _res = execute(deps.as_mut(), env.clone(), seller_info, propose(2, 5_000)).unwrap();
+    let msg = ExecuteMsg::SignReceived { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), buyer_info, msg).unwrap();
+    let msg = QueryMsg::Settlements { order_id: 2 };
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: SettlementsResponse = from_json(&bin).unwrap();
+    assert!(res.settlements.is_empty());
+}
+
+//Test that the admin manages arbiters in the registry and their fee caps rulings
+#[test]
+fn test_arbiter_registry() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let admin_info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+
+    let mediator = deps.api.addr_make("mediator");
+    let seller = deps.api.addr_make("seller");
+    let buyer = deps.api.addr_make("buyer");
+    let mediator_info = message_info(&mediator, &[]);
+    let add = |fee_bps| ExecuteMsg::AddArbiter {
+        address: mediator.to_string(),
+        name: "Mediator".to_string(),
+        fee_bps,
+    };
+    let err = execute(deps.as_mut(), env.clone(), mediator_info.clone(), add(200)).unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+    let err = execute(deps.as_mut(), env.clone(), admin_info.clone(), add(2_001)).unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::ArbiterFeeTooHigh { fee_bps: 2_000 }
+    ));
+    let _res = execute(deps.as_mut(), env.clone(), admin_info.clone(), add(200)).unwrap();
+    let msg = QueryMsg::Arbiters {
+        limit: None,
+        start_after: None,
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ArbitersResponse = from_json(&bin).unwrap();
+    assert_eq!(res.arbiters.len(), 2);
+    let msg = QueryMsg::Arbiter {
+        address: "not an address".to_string(),
+    };
+    assert!(query(deps.as_ref(), env.clone(), msg).is_err());
+
+    //an active arbiter can sign shipped for the seller
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Film Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Light seals replaced".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 1_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&seller, &[]), msg).unwrap();
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let info = message_info(&buyer, &[coin(1_000_000, JUNO)]);
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), mediator_info.clone(), msg).unwrap();
+    let msg = ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), message_info(&buyer, &[]), msg).unwrap();
+
+    let arbitrate = |arbiter_bps| ExecuteMsg::Arbitrate {
+        order_id: 1,
+        ruling: RulingMsg {
+            buyer_bps: 5_000,
+            seller_bps: 5_000 - arbiter_bps,
+            arbiter_bps,
+        },
+        milestone_id: None,
+    };
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mediator_info.clone(),
+        arbitrate(500),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::ArbiterFeeTooHigh { fee_bps: 200 }
+    ));
+
+    //removed arbiters stay listed but can't rule
+    let msg = ExecuteMsg::RemoveArbiter {
+        address: mediator.to_string(),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), admin_info.clone(), msg).unwrap();
+    let msg = QueryMsg::Arbiter {
+        address: mediator.to_string(),
+    };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: ArbiterResponse = from_json(&bin).unwrap();
+    let arbiter = res.arbiter.unwrap();
+    assert_eq!(arbiter.name, "Mediator");
+    assert!(!arbiter.active);
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mediator_info.clone(),
+        arbitrate(200),
+    )
+    .unwrap_err();
+    assert!(matches!(err, ContractError::Unauthorized {}));
+
+    //adding it back reactivates it with the new fee, which a lower marketplace cap still overrides
+    let _res = execute(deps.as_mut(), env.clone(), admin_info.clone(), add(500)).unwrap();
+    let msg = ExecuteMsg::UpdateConfig {
+        fee_bps: None,
+        fee_recipients: None,
+        accepted_denoms: None,
+        gateway: None,
+        max_text_length: None,
+        max_id_length: None,
+        push_payouts: None,
+        confirmation_window: None,
+        ship_window: None,
+        max_arbiter_fee_bps: Some(400),
+    };
+    let _res = execute(deps.as_mut(), env.clone(), admin_info, msg).unwrap();
+    let err = execute(
+        deps.as_mut(),
+        env.clone(),
+        mediator_info.clone(),
+        arbitrate(500),
+    )
+    .unwrap_err();
+    assert!(matches!(
+        err,
+        ContractError::ArbiterFeeTooHigh { fee_bps: 400 }
+    ));
+    let res = execute(deps.as_mut(), env, mediator_info, arbitrate(400)).unwrap();
+    assert!(res.attributes.contains(&attr("to arbiter", "40000")));
+    assert!(res.attributes.contains(&attr("amount to seller", "437000")));
+}
+
+//Test that escrowed and owed funds are tracked and only stray funds can be swept
+#[test]
+fn test_escrow_summary_and_sweep() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let listing_buyer = deps.api.addr_make("listing_buyer");
+
+    for _ in 0..2 {
+        let info = message_info(&listing_creator, &[]);
+        let msg = ExecuteMsg::CreateListing {
+            listing_title: "Vintage Camera".to_string(),
+            external_id: IPFS_LINK.to_string(),
+            text: "Selling my vintage camera in excellent condition".to_string(),
+            tags: vec!["Camera".to_string()],
+            contact: "Signal: +1234567890".to_string(),
+            price: 100_000_000,
+            denom: JUNO.to_string(),
+            quantity: 1,
+            variants: vec![],
+            shipping_options: vec![],
+            ship_within: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+    for listing_id in 1..=2 {
+        let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
+        let msg = ExecuteMsg::Purchase {
+            listing_id,
+            variant_id: None,
+            shipping_id: None,
+        };
+        let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    }
+    let info = message_info(&listing_creator, &[]);
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let info = message_info(&listing_buyer, &[]);
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
+    //one purchase is still escrowed, the other was credited to the ledger,
+    //on top of that someone sent juno and an unrelated denom straight to the contract
+    deps.querier.bank.update_balance(
+        env.contract.address.clone(),
+        vec![coin(200_000_123, JUNO), coin(42, USDC)],
+    );
+    let bin = query(deps.as_ref(), env.clone(), QueryMsg::EscrowSummary {}).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
     assert_eq!(
-        res.attributes,
+        res.denoms,
         vec![
-            attr("action", "instantiate"),
-            attr("admin", instantiator.to_string())
+            DenomEscrowSummary {
+                denom: USDC.to_string(),
+                contract_balance: Uint128::new(42),
+                escrowed: Uint128::zero(),
+                owed: Uint128::zero(),
+                excess: Uint128::new(42),
+                shortfall: Uint128::zero(),
+            },
+            DenomEscrowSummary {
+                denom: JUNO.to_string(),
+                contract_balance: Uint128::new(200_000_123),
+                escrowed: Uint128::new(100_000_000),
+                owed: Uint128::new(100_000_000),
+                excess: Uint128::new(123),
+                shortfall: Uint128::zero(),
+            },
         ]
     );
+
+    //only the admin can sweep
+    let info = message_info(&listing_buyer, &[]);
+    let msg = ExecuteMsg::SweepExcess {
+        denom: JUNO.to_string(),
+        recipient: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "Unauthorized");
+
+    let info = message_info(&instantiator, &[]);
+    let res = execute(deps.as_mut(), env.clone(), info.clone(), msg.clone()).unwrap();
+    assert_eq!(
+        res.messages[0].msg,
+        CosmosMsg::Bank(BankMsg::Send {
+            to_address: instantiator.to_string(),
+            amount: vec![coin(123, JUNO)],
+        })
+    );
+
+    //once the stray funds are gone there is nothing left to sweep
+    deps.querier
+        .bank
+        .update_balance(env.contract.address.clone(), vec![coin(200_000_000, JUNO)]);
+    let err = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap_err();
+    assert_eq!(err.to_string(), "Nothing to sweep for ujuno");
+
+    //withdrawals and refunds release the tracked totals
+    let info = message_info(&listing_creator, &[]);
+    let msg = ExecuteMsg::Withdraw {
+        denom: JUNO.to_string(),
+        amount: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let info = message_info(&listing_buyer, &[]);
+    let msg = ExecuteMsg::CancelPurchase { order_id: 2 };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    //the admin fee and the buyer's refund are now both waiting on the ledger
+    deps.querier
+        .bank
+        .update_balance(env.contract.address.clone(), vec![coin(105_000_000, JUNO)]);
+    let bin = query(deps.as_ref(), env.clone(), QueryMsg::EscrowSummary {}).unwrap();
+    let res: EscrowSummaryResponse = from_json(&bin).unwrap();
+    assert_eq!(
+        res.denoms,
+        vec![DenomEscrowSummary {
+            denom: JUNO.to_string(),
+            contract_balance: Uint128::new(105_000_000),
+            escrowed: Uint128::zero(),
+            owed: Uint128::new(105_000_000),
+            excess: Uint128::zero(),
+            shortfall: Uint128::zero(),
+        }]
+    );
 }
 
 //Test that the contract can be migrated
@@ -42,12 +3233,20 @@ fn migrate_works() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env, info, msg).unwrap();
 
     //migrate
     let msg = MigrateMsg {};
     let _res: Response = migrate(deps.as_mut(), mock_env(), msg).unwrap();
+
+    //state from before orders existed can't be carried over
+    cw2::set_contract_version(deps.as_mut().storage, "julian", "0.0.1").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Generic error: Cannot migrate from 0.0.1, state written before 0.1.0 is not compatible"
+    );
 }
 
 //Test that a listing can be created and then queried
@@ -59,7 +3258,7 @@ fn test_execute_create_listing_valid() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -78,6 +3277,11 @@ fn test_execute_create_listing_valid() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -100,11 +3304,7 @@ fn test_execute_create_listing_valid() {
     assert_eq!(listing.listing_title, "Vintage Camera");
     assert_eq!(listing.seller, listing_creator.to_string());
     assert_eq!(listing.price, 100_000_000);
-    assert!(!listing.bought);
-    assert!(!listing.shipped);
-    assert!(!listing.received);
-    assert!(!listing.arbitration_requested);
-    assert_eq!(listing.buyer, None);
+    assert_eq!(listing.status, ListingStatus::Active);
 }
 
 //instantiate, create a listing, have a different address purchase it, then query to ensure the listing state changes
@@ -116,7 +3316,7 @@ fn test_purchase_flow() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -136,12 +3336,21 @@ fn test_purchase_flow() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Purchase listing with different address
     let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
-    let msg = ExecuteMsg::Purchase { listing_id: 1 };
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Verify purchase attributes
@@ -150,18 +3359,35 @@ fn test_purchase_flow() {
         vec![
             attr("action", "purchase"),
             attr("post_id", "1"),
+            attr("order_id", "1"),
             attr("buyer", listing_buyer.clone()),
+            attr("payment_denom", JUNO),
+            attr("payment_required", "100000000"),
+            attr("payment_received", "100000000"),
+            attr("payment_overpayment", "0"),
+            attr("payment_refunded", "none"),
         ]
     );
+    assert!(res.messages.is_empty());
 
     // Query the listing to verify purchase state
     let msg = QueryMsg::Listing { listing_id: 1 };
-    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
     let res: ListingResponse = from_json(&bin).unwrap();
 
     let listing = res.listing.unwrap();
-    assert!(listing.bought);
-    assert_eq!(listing.buyer, Some(listing_buyer.to_string()));
+    assert_eq!(listing.status, ListingStatus::SoldOut);
+
+    // The purchase opened an order holding the price in escrow
+    let msg = QueryMsg::Order { order_id: 1 };
+    let bin = query(deps.as_ref(), env, msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    let order = res.order.unwrap();
+    assert_eq!(order.listing_id, Some(1));
+    assert_eq!(order.buyer, listing_buyer);
+    assert_eq!(order.seller, listing_creator);
+    assert_eq!(order.price, 100_000_000);
+    assert_eq!(order.status, OrderStatus::Purchased);
 }
 
 //Test purchase, sign shipped, and sign received
@@ -173,7 +3399,7 @@ fn test_purchase_sign_shipped_sign_received() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -193,22 +3419,31 @@ fn test_purchase_sign_shipped_sign_received() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     //Buyer purchases listing
     let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
-    let msg = ExecuteMsg::Purchase { listing_id: 1 };
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Seller signs shipped
     let info = message_info(&listing_creator, &[]);
-    let msg = ExecuteMsg::SignShipped { listing_id: 1 };
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Buyer signs received
     let info = message_info(&listing_buyer, &[]);
-    let msg = ExecuteMsg::SignReceived { listing_id: 1 };
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Verify that profiles don't exist
@@ -227,6 +3462,215 @@ fn test_purchase_sign_shipped_sign_received() {
     assert!(res.profile.is_none());
 }
 
+//Test that overpayment and unrelated denoms are refunded to the buyer in the purchase response
+#[test]
+fn test_purchase_refunds_overpayment() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let info = message_info(&instantiator, &[]);
+
+    let msg = default_instantiate_msg(&instantiator);
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let listing_buyer = deps.api.addr_make("listing_buyer");
+
+    let info = message_info(&listing_creator, &[]);
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Vintage Camera".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "Selling my vintage camera in excellent condition".to_string(),
+        tags: vec!["Camera".to_string()],
+        contact: "Signal: +1234567890".to_string(),
+        price: 100_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //underpaying fails even when other denoms are attached
+    let info = message_info(
+        &listing_buyer,
+        &[coin(60_000_000, JUNO), coin(50_000_000, USDC)],
+    );
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
+    assert_eq!(
+        err.to_string(),
+        "Insufficient funds. Needed: 100000000ujuno Sent: 60000000ujuno,50000000ibc/usdc"
+    );
+
+    //split juno coins are summed, the excess and the usdc are sent back
+    let info = message_info(
+        &listing_buyer,
+        &[
+            coin(60_000_000, JUNO),
+            coin(50_000_000, USDC),
+            coin(45_000_000, JUNO),
+        ],
+    );
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
+    let res = execute(deps.as_mut(), env, info, msg).unwrap();
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|sub_msg| sub_msg.msg)
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: listing_buyer.to_string(),
+                amount: vec![coin(5_000_000, JUNO)],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: listing_buyer.to_string(),
+                amount: vec![coin(50_000_000, USDC)],
+            }),
+        ]
+    );
+    assert!(res
+        .attributes
+        .contains(&attr("payment_received", "105000000")));
+    assert!(res
+        .attributes
+        .contains(&attr("payment_overpayment", "5000000")));
+    assert!(res
+        .attributes
+        .contains(&attr("payment_refunded", "5000000ujuno,50000000ibc/usdc")));
+}
+
+//Test the weighted fee split on release and its preview query, with rounding dust going to the first recipient
+#[test]
+fn test_fee_schedule_split() {
+    let mut deps = mock_dependencies();
+    let env = mock_env();
+    let instantiator = deps.api.addr_make("instantiator");
+    let treasury = deps.api.addr_make("treasury");
+    let arbiter_pool = deps.api.addr_make("arbiter_pool");
+    let community = deps.api.addr_make("community");
+    let info = message_info(&instantiator, &[]);
+
+    let mut msg = default_instantiate_msg(&instantiator);
+    msg.fee_bps = 250;
+    msg.push_payouts = true;
+    msg.fee_recipients = vec![
+        FeeRecipientMsg {
+            name: "treasury".to_string(),
+            address: treasury.to_string(),
+            weight: 1,
+        },
+        FeeRecipientMsg {
+            name: "arbiters".to_string(),
+            address: arbiter_pool.to_string(),
+            weight: 1,
+        },
+        FeeRecipientMsg {
+            name: "community".to_string(),
+            address: community.to_string(),
+            weight: 1,
+        },
+    ];
+    let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
+
+    //2.5% of 1000 = 25, split three ways is 8 each with 1 left over for the treasury
+    let msg = QueryMsg::FeePreview { price: 1_000 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: FeePreviewResponse = from_json(&bin).unwrap();
+    assert_eq!(res.split.seller_amount.u128(), 975);
+    assert_eq!(res.split.fee_amount.u128(), 25);
+    assert_eq!(
+        res.split
+            .shares
+            .iter()
+            .map(|share| share.amount.u128())
+            .collect::<Vec<_>>(),
+        vec![9, 8, 8]
+    );
+
+    let listing_creator = deps.api.addr_make("listing_creator");
+    let listing_buyer = deps.api.addr_make("listing_buyer");
+    let info = message_info(&listing_creator, &[]);
+    let msg = ExecuteMsg::CreateListing {
+        listing_title: "Sticker".to_string(),
+        external_id: IPFS_LINK.to_string(),
+        text: "A sticker".to_string(),
+        tags: vec![],
+        contact: "Signal: +1234567890".to_string(),
+        price: 1_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
+    };
+    let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
+    let info = message_info(&listing_buyer, &[coin(1_000, JUNO)]);
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::Purchase {
+            listing_id: 1,
+            variant_id: None,
+            shipping_id: None,
+        },
+    )
+    .unwrap();
+    let info = message_info(&listing_creator, &[]);
+    let _res = execute(
+        deps.as_mut(),
+        env.clone(),
+        info,
+        ExecuteMsg::SignShipped { order_id: 1 },
+    )
+    .unwrap();
+    let info = message_info(&listing_buyer, &[]);
+    let res = execute(
+        deps.as_mut(),
+        env,
+        info,
+        ExecuteMsg::SignReceived { order_id: 1 },
+    )
+    .unwrap();
+
+    assert_eq!(
+        res.messages
+            .into_iter()
+            .map(|sub_msg| sub_msg.msg)
+            .collect::<Vec<_>>(),
+        vec![
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: listing_creator.to_string(),
+                amount: vec![coin(975, JUNO)],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: treasury.to_string(),
+                amount: vec![coin(9, JUNO)],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: arbiter_pool.to_string(),
+                amount: vec![coin(8, JUNO)],
+            }),
+            CosmosMsg::Bank(BankMsg::Send {
+                to_address: community.to_string(),
+                amount: vec![coin(8, JUNO)],
+            }),
+        ]
+    );
+    assert!(res.attributes.contains(&attr("fee to community", "8")));
+}
+
 //Test Canceling a purchase
 #[test]
 fn test_cancel_purchase() {
@@ -236,7 +3680,7 @@ fn test_cancel_purchase() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -256,17 +3700,26 @@ fn test_cancel_purchase() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Purchase listing with different address
     let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
-    let msg = ExecuteMsg::Purchase { listing_id: 1 };
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Cancel purchase
     let info = message_info(&listing_buyer, &[]);
-    let msg = ExecuteMsg::CancelPurchase { listing_id: 1 };
+    let msg = ExecuteMsg::CancelPurchase { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Verify that the purchase was canceled
@@ -274,11 +3727,7 @@ fn test_cancel_purchase() {
     let bin = query(deps.as_ref(), env, msg).unwrap();
     let res: ListingResponse = from_json(&bin).unwrap();
     let listing = res.listing.unwrap();
-    assert!(!listing.bought);
-    assert!(!listing.shipped);
-    assert!(!listing.received);
-    assert!(!listing.arbitration_requested);
-    assert_eq!(listing.buyer, None);
+    assert_eq!(listing.status, ListingStatus::Active);
 }
 
 #[test]
@@ -290,7 +3739,7 @@ fn test_execute_edit_post_valid() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -309,6 +3758,11 @@ fn test_execute_edit_post_valid() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -325,6 +3779,9 @@ fn test_execute_edit_post_valid() {
             "Case".to_string(),
         ], //Added case tag
         price: 120_000_000, // Increased price to 120 JUNO
+        variants: None,
+        shipping_options: None,
+        ship_within: None,
     };
     let res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -357,7 +3814,7 @@ fn test_execute_edit_post_invalid() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -377,6 +3834,11 @@ fn test_execute_edit_post_invalid() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -393,6 +3855,9 @@ fn test_execute_edit_post_invalid() {
             "Case".to_string(),
         ], //Added case tag
         price: 120_000_000, // Increased price to 120 JUNO
+        variants: None,
+        shipping_options: None,
+        ship_within: None,
     };
     let _err = execute(deps.as_mut(), env, info, msg).unwrap_err();
 }
@@ -404,7 +3869,7 @@ fn test_execute_delete_post_valid() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -423,6 +3888,11 @@ fn test_execute_delete_post_valid() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -444,7 +3914,7 @@ fn test_execute_delete_post_invalid() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -464,6 +3934,11 @@ fn test_execute_delete_post_invalid() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -481,7 +3956,7 @@ fn test_execute_arbitrate_post_valid() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -501,37 +3976,60 @@ fn test_execute_arbitrate_post_valid() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     //Buyer purchases listing
     let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
-    let msg = ExecuteMsg::Purchase { listing_id: 1 };
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Seller signs shipped
     let info = message_info(&listing_creator, &[]);
-    let msg = ExecuteMsg::SignShipped { listing_id: 1 };
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Buyer requests arbitration
     let info = message_info(&listing_buyer, &[]);
-    let msg = ExecuteMsg::RequestArbitration { listing_id: 1 };
+    let msg = ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Arbiter arbitrates and returns funds to buyer
     let info = message_info(&instantiator, &[]);
     let msg = ExecuteMsg::Arbitrate {
-        listing_id: 1,
-        funds_recipient: listing_buyer.to_string(),
+        order_id: 1,
+        ruling: RulingMsg {
+            buyer_bps: 10_000,
+            seller_bps: 0,
+            arbiter_bps: 0,
+        },
+        milestone_id: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
-    //Verify that the listing was deleted
+    //Verify that the order was refunded and the listing stays closed
+    let msg = QueryMsg::Order { order_id: 1 };
+    let bin = query(deps.as_ref(), env.clone(), msg).unwrap();
+    let res: OrderResponse = from_json(&bin).unwrap();
+    let order = res.order.unwrap();
+    assert_eq!(order.status, OrderStatus::Refunded);
+    assert_eq!(order.refunded_at, Some(env.block.time));
     let msg = QueryMsg::Listing { listing_id: 1 };
     let bin = query(deps.as_ref(), env, msg).unwrap();
     let res: ListingResponse = from_json(&bin).unwrap();
-    assert!(res.listing.is_none());
+    assert_eq!(res.listing.unwrap().status, ListingStatus::SoldOut);
 }
 
 #[test]
@@ -544,13 +4042,12 @@ fn test_execute_arbitrate_post_invalid() {
     println!("Instantiator address: {}", instantiator);
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
     let listing_creator = deps.api.addr_make("listing_creator");
     let listing_buyer = deps.api.addr_make("listing_buyer");
-    let random_address = deps.api.addr_make("random_address");
 
     // Create listing with required JUNO payment
     let info = message_info(&listing_creator, &[]);
@@ -565,34 +4062,51 @@ fn test_execute_arbitrate_post_invalid() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     //Buyer purchases listing
     let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
-    let msg = ExecuteMsg::Purchase { listing_id: 1 };
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Seller signs shipped
     let info = message_info(&listing_creator, &[]);
-    let msg = ExecuteMsg::SignShipped { listing_id: 1 };
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Buyer requests arbitration
     let info = message_info(&listing_buyer, &[]);
-    let msg = ExecuteMsg::RequestArbitration { listing_id: 1 };
+    let msg = ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Arbiter arbitrates and attempts to send funds to random address
     let info = message_info(&instantiator, &[]);
     let msg = ExecuteMsg::Arbitrate {
-        listing_id: 1,
-        funds_recipient: random_address.to_string(),
+        order_id: 1,
+        ruling: RulingMsg {
+            buyer_bps: 5_000,
+            seller_bps: 4_000,
+            arbiter_bps: 0,
+        },
+        milestone_id: None,
     };
     let _err = execute(deps.as_mut(), env.clone(), info, msg).unwrap_err();
     assert_eq!(
         _err.to_string(),
-        "Funds recipient must be the seller or buyer"
+        "Ruling shares must add up to 10000 basis points"
     );
 }
 
@@ -605,7 +4119,7 @@ fn test_query_all_listings() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -624,6 +4138,11 @@ fn test_query_all_listings() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -640,6 +4159,11 @@ fn test_query_all_listings() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -669,7 +4193,7 @@ fn test_query_listing() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -688,6 +4212,11 @@ fn test_query_listing() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -711,7 +4240,7 @@ fn test_query_article_count() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -730,6 +4259,11 @@ fn test_query_article_count() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
     //query article count
@@ -745,7 +4279,7 @@ fn test_query_arbitration_listings() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -765,22 +4299,34 @@ fn test_query_arbitration_listings() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000, // 100 JUNO
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
     //Buyer purchases listing
     let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
-    let msg = ExecuteMsg::Purchase { listing_id: 1 };
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Seller signs shipped
     let info = message_info(&listing_creator, &[]);
-    let msg = ExecuteMsg::SignShipped { listing_id: 1 };
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Buyer requests arbitration
     let info = message_info(&listing_buyer, &[]);
-    let msg = ExecuteMsg::RequestArbitration { listing_id: 1 };
+    let msg = ExecuteMsg::RequestArbitration {
+        order_id: 1,
+        milestone_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //Query arbitration listings
@@ -790,7 +4336,8 @@ fn test_query_arbitration_listings() {
     };
     let bin = query(deps.as_ref(), env, msg).unwrap();
     let res: ArbitrationListingsResponse = from_json(&bin).unwrap();
-    assert_eq!(res.listings.len(), 1);
+    assert_eq!(res.orders.len(), 1);
+    assert_eq!(res.orders[0].status, OrderStatus::Disputed);
 }
 
 #[test]
@@ -801,7 +4348,7 @@ fn test_query_listings_by_title() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     //create mock addresses
@@ -820,6 +4367,11 @@ fn test_query_listings_by_title() {
         ],
         contact: "Signal: +1234567890".to_string(),
         price: 100_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -831,6 +4383,11 @@ fn test_query_listings_by_title() {
         tags: vec!["Electronics".to_string(), "Camera".to_string()],
         contact: "Signal: +1234567890".to_string(),
         price: 50_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info.clone(), msg).unwrap();
 
@@ -842,6 +4399,11 @@ fn test_query_listings_by_title() {
         tags: vec!["Electronics".to_string(), "Phone".to_string()],
         contact: "Signal: +1234567890".to_string(),
         price: 75_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
@@ -886,7 +4448,7 @@ fn test_cleanup_old_relationships() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     let listing_creator = deps.api.addr_make("listing_creator");
@@ -901,17 +4463,26 @@ fn test_cleanup_old_relationships() {
         tags: vec!["test".to_string()],
         contact: "test@test.com".to_string(),
         price: 100_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Purchase listing
     let info = message_info(&listing_buyer, &[coin(100_000_000, JUNO)]);
-    let msg = ExecuteMsg::Purchase { listing_id: 1 };
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Sign shipped (creates relationship)
     let info = message_info(&listing_creator, &[]);
-    let msg = ExecuteMsg::SignShipped { listing_id: 1 };
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Move time forward 31 days
@@ -939,7 +4510,7 @@ fn test_rating_system() {
     let instantiator = deps.api.addr_make("instantiator");
     let info = message_info(&instantiator, &[]);
 
-    let msg = InstantiateMsg {};
+    let msg = default_instantiate_msg(&instantiator);
     let _res = instantiate(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     let seller = deps.api.addr_make("seller");
@@ -967,22 +4538,31 @@ fn test_rating_system() {
         tags: vec!["test".to_string()],
         contact: "test@test.com".to_string(),
         price: 100_000_000,
+        denom: JUNO.to_string(),
+        quantity: 1,
+        variants: vec![],
+        shipping_options: vec![],
+        ship_within: None,
     };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Buyer purchases the item
     let info = message_info(&buyer, &[coin(100_000_000, JUNO)]);
-    let msg = ExecuteMsg::Purchase { listing_id: 1 };
+    let msg = ExecuteMsg::Purchase {
+        listing_id: 1,
+        variant_id: None,
+        shipping_id: None,
+    };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Seller marks as shipped
     let info = message_info(&seller, &[]);
-    let msg = ExecuteMsg::SignShipped { listing_id: 1 };
+    let msg = ExecuteMsg::SignShipped { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Buyer marks as received
     let info = message_info(&buyer, &[]);
-    let msg = ExecuteMsg::SignReceived { listing_id: 1 };
+    let msg = ExecuteMsg::SignReceived { order_id: 1 };
     let _res = execute(deps.as_mut(), env.clone(), info, msg).unwrap();
 
     // Seller rates buyer 5 stars